@@ -0,0 +1,89 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use wasmlanche::{
+    ensure,
+    estimate::{borsh_size_of, MAX_STATE_VALUE_SIZE},
+    public, state_schema, Address, Context, ErrorCode, Revert,
+};
+
+/// Returned when a memo is larger than a stored value can be.
+pub const MEMO_TOO_LARGE: ErrorCode = ErrorCode::new(1, "memo does not fit in state");
+/// Returned when the call does not have the gas left to store the memo and return.
+pub const OUT_OF_GAS: ErrorCode = ErrorCode::new(2, "not enough gas to store the memo");
+
+state_schema! {
+    /// The memo each address left.
+    Memo(Address) => Vec<String>,
+}
+
+/// Gets the memo left by the address.
+#[public]
+pub fn get_memo(context: &mut Context, of: Address) -> Vec<String> {
+    context
+        .get(Memo(of))
+        .expect("state corrupt")
+        .unwrap_or_default()
+}
+
+/// Leaves a memo for the actor, checking that it can be stored before storing it so that an
+/// oversized memo reverts instead of trapping when the state is written.
+#[public]
+pub fn set_memo(context: &mut Context, lines: Vec<String>) -> Result<(), Revert> {
+    let size = borsh_size_of(&lines);
+    ensure!(
+        size <= MAX_STATE_VALUE_SIZE,
+        MEMO_TOO_LARGE,
+        "the memo takes {size} bytes, at most {MAX_STATE_VALUE_SIZE} fit"
+    );
+    ensure!(
+        context.can_afford_state_write(size) && context.can_afford_return(),
+        OUT_OF_GAS
+    );
+
+    let actor = context.actor();
+    context
+        .store_by_key(Memo(actor), lines)
+        .expect("serialization failed");
+
+    Ok(())
+}
+
+#[cfg(test)]
+#[cfg(not(feature = "bindings"))]
+mod tests {
+    use super::*;
+    use wasmlanche::estimate::STATE_WRITE_COST;
+
+    #[test]
+    fn stores_memos_that_fit() {
+        let address = Address::default();
+        let mut context = Context::with_actor(address);
+        let lines = vec![String::from("hello"), String::from("world")];
+
+        set_memo(&mut context, lines.clone()).unwrap();
+        assert_eq!(get_memo(&mut context, address), lines);
+    }
+
+    #[test]
+    fn rejects_oversized_memos() {
+        let address = Address::default();
+        let mut context = Context::with_actor(address);
+        let lines = vec!["x".repeat(MAX_STATE_VALUE_SIZE)];
+
+        let err = set_memo(&mut context, lines).unwrap_err();
+        assert_eq!(err.code(), MEMO_TOO_LARGE.code());
+        assert!(get_memo(&mut context, address).is_empty());
+    }
+
+    #[test]
+    fn rejects_memos_it_cannot_afford() {
+        let address = Address::default();
+        let mut context = Context::with_actor(address);
+        context.mock_remaining_fuel(STATE_WRITE_COST - 1);
+
+        let err = set_memo(&mut context, vec![String::from("hi")]).unwrap_err();
+        assert_eq!(err, OUT_OF_GAS.into());
+        assert!(get_memo(&mut context, address).is_empty());
+    }
+}
@@ -4,25 +4,109 @@
 extern crate proc_macro;
 
 use proc_macro::TokenStream;
-use syn::{parse_macro_input, punctuated::Punctuated, Expr, Token};
+use syn::{parse_macro_input, punctuated::Punctuated, Expr, ItemFn, ItemMod, Token};
 
 mod public;
+mod public_dispatch;
 mod state_schema;
 mod to_pairs;
+mod when_not_paused;
 
-use public::{impl_public, PublicFn};
+use public::{impl_public, PublicFn, PublicOptions};
+use public_dispatch::impl_public_dispatch;
 use state_schema::{impl_state_schema, KeyPair};
 use to_pairs::to_pairs;
+use when_not_paused::impl_when_not_paused;
 
 /// The `public` attribute macro will make the function you attach it to an entry-point for your smart-contract.
 /// `#[public]` functions must have `pub` visibility and the first parameter must be of type `Context`.
 /// They can have any number of additional parameters that implement `BorshSerialize` + `BorshDeserialize`.
 /// The return type must also implement `BorshSerialize` + `BorshDeserialize`.
+///
+/// Trailing parameters of type `Option<T>`, or marked with `#[default(expr)]`, may be left out by callers.
+/// When the input ends before such a parameter it is decoded as `None` or `expr` respectively,
+/// so parameters can be appended to a function without breaking existing callers.
+/// Use `#[public(strict_args)]` to reject short input instead.
+/// ```
+/// # use wasmlanche::{public, Context};
+/// #[public]
+/// pub fn transfer(_: &mut Context, amount: u64, _memo: Option<String>, #[default(1)] repeat: u8) -> u64 {
+///     amount * u64::from(repeat)
+/// }
+/// # fn main() {}
+/// ```
 #[proc_macro_attribute]
-pub fn public(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn public(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let options = parse_macro_input!(attr as PublicOptions);
     let input = parse_macro_input!(item as PublicFn);
 
-    match impl_public(input) {
+    match impl_public(input, &options) {
+        Ok(token_stream) => token_stream,
+        Err(err) => err.to_compile_error(),
+    }
+    .into()
+}
+
+/// The `public_dispatch` attribute turns an action enum into a single `execute` entry-point.
+/// It is applied to an inline module declaring exactly one enum and an `impl` block for it.
+/// Every variant `FooBar` must have a handler `fn foo_bar(context: &mut Context, ..)` taking the
+/// variant's fields in declaration order; a missing handler is a compile error.
+///
+/// The macro generates:
+/// - `Action::dispatch(self, context)` which calls the matching handler
+/// - a `#[public]` function `execute(context, action)` that dispatches and returns the handler's result
+/// - a `#[public]` function per variant, named after its handler, for callers who prefer direct calls
+/// ```
+/// # use wasmlanche::{borsh::{BorshDeserialize, BorshSerialize}, public_dispatch, Context};
+/// #[public_dispatch]
+/// mod actions {
+///     use super::*;
+///
+///     #[derive(BorshSerialize, BorshDeserialize)]
+///     #[borsh(crate = "wasmlanche::borsh")]
+///     pub enum Action {
+///         Increment(u64),
+///         Reset,
+///     }
+///
+///     impl Action {
+///         fn increment(_: &mut Context, amount: u64) -> u64 {
+///             amount + 1
+///         }
+///
+///         fn reset(_: &mut Context) {}
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[proc_macro_attribute]
+pub fn public_dispatch(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemMod);
+
+    match impl_public_dispatch(input) {
+        Ok(token_stream) => token_stream,
+        Err(err) => err.to_compile_error(),
+    }
+    .into()
+}
+
+/// The `when_not_paused` attribute makes a function revert with `pause::PAUSED` while the contract
+/// is paused with `Context::pause`. Stack it below `#[public]`. The function must return a
+/// `Result` whose error converts from `Revert`.
+/// ```
+/// # use wasmlanche::{public, when_not_paused, Context, Revert};
+/// #[public]
+/// #[when_not_paused]
+/// pub fn transfer(_: &mut Context, amount: u64) -> Result<u64, Revert> {
+///     Ok(amount)
+/// }
+/// # fn main() {}
+/// ```
+#[proc_macro_attribute]
+pub fn when_not_paused(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+
+    match impl_when_not_paused(input) {
         Ok(token_stream) => token_stream,
         Err(err) => err.to_compile_error(),
     }
@@ -62,6 +146,7 @@ pub fn public(_attr: TokenStream, item: TokenStream) -> TokenStream {
 /// Each pair is used to automatically generate a `Schema` implementation for the key type with the value-type ass the associated `Schema::Value`.
 /// For now, the keys are prefixed with `u8` to prevent collisions. This will likely change in the future, but that means you
 /// absolutely should not use [state_schema!] more than one for a particular smart contract.
+/// A schema holds at most `u8::MAX` keys, the last prefix is reserved for the flag kept by `Context::pause`.
 #[proc_macro]
 pub fn state_schema(input: TokenStream) -> TokenStream {
     let key_pairs =
@@ -8,37 +8,122 @@ use syn::{
     parse_quote, parse_str,
     punctuated::Punctuated,
     spanned::Spanned,
-    Block, Error, FnArg, Generics, Ident, ItemFn, Pat, PatIdent, PatType, PatWild, ReturnType,
-    Signature, Token, Type, TypeReference, Visibility,
+    Attribute, Block, Error, Expr, FnArg, GenericArgument, Generics, Ident, ItemFn, Pat, PatIdent,
+    PatType, PatWild, PathArguments, ReturnType, Signature, Token, Type, TypePath, TypeReference,
+    Visibility,
 };
 
 const CONTEXT_TYPE: &str = "&mut wasmlanche::Context";
+const DEFAULT_ATTR: &str = "default";
+const STRICT_ARGS: &str = "strict_args";
 
 type CommaSeparated<T> = Punctuated<T, Token![,]>;
 
-pub fn impl_public(public_fn: PublicFn) -> Result<TokenStream, Error> {
+/// Options passed to the attribute itself, e.g. `#[public(strict_args)]`.
+#[derive(Default)]
+pub struct PublicOptions {
+    /// Reject short input instead of filling in trailing defaults.
+    strict_args: bool,
+}
+
+impl Parse for PublicOptions {
+    fn parse(input: ParseStream) -> Result<Self, Error> {
+        let mut options = Self::default();
+
+        for option in CommaSeparated::<Ident>::parse_terminated(input)? {
+            if option == STRICT_ARGS {
+                options.strict_args = true;
+            } else {
+                return Err(Error::new(
+                    option.span(),
+                    format!("unknown `#[public]` option `{option}`, expected `{STRICT_ARGS}`"),
+                ));
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+pub fn impl_public(public_fn: PublicFn, options: &PublicOptions) -> Result<TokenStream, Error> {
     let args_names = public_fn
         .sig
         .other_inputs
         .iter()
         .map(|PatType { pat: name, .. }| quote! {#name});
-    let args_names_2 = args_names.clone();
+    let call_args = public_fn
+        .sig
+        .other_inputs
+        .iter()
+        .map(|PatType { pat: name, ty, .. }| match Borrowed::of(ty) {
+            Some(Borrowed::Str) => quote! { &#name },
+            Some(Borrowed::Cow) | None => quote! { #name },
+        });
 
     let name = &public_fn.sig.ident;
     let context_type = type_from_reference(&public_fn.sig.user_defined_context_type);
 
-    let other_inputs = public_fn.sig.other_inputs.iter();
+    let other_inputs = public_fn.sig.other_inputs.iter().map(owned_input);
+
+    let lenient = !options.strict_args && public_fn.trailing_defaults.iter().any(Option::is_some);
+
+    let (args_derive, decode_args) = if lenient {
+        let decode_fields = public_fn
+            .sig
+            .other_inputs
+            .iter()
+            .zip(&public_fn.trailing_defaults)
+            .map(|(PatType { pat: name, .. }, default)| match default {
+                Some(default) => quote! {
+                    #name: wasmlanche::args::decode_trailing_arg(buf, || #default)?
+                },
+                None => quote! {
+                    #name: wasmlanche::borsh::BorshDeserialize::deserialize(buf)?
+                },
+            });
+
+        let decode_args = quote! {
+            impl Args {
+                fn from_slice(mut bytes: &[u8]) -> wasmlanche::borsh::io::Result<Self> {
+                    let buf = &mut bytes;
+                    // fields are decoded in the order they are written
+                    let args = Self {
+                        ctx: wasmlanche::borsh::BorshDeserialize::deserialize(buf)?,
+                        #(#decode_fields),*
+                    };
+                    wasmlanche::args::finish_args(buf)?;
+                    Ok(args)
+                }
+            }
+        };
+
+        (TokenStream::new(), decode_args)
+    } else {
+        let args_derive = quote! {
+            #[derive(wasmlanche::borsh::BorshDeserialize)]
+            #[borsh(crate = "wasmlanche::borsh")]
+        };
+
+        (args_derive, TokenStream::new())
+    };
+
+    let from_slice = if lenient {
+        quote! { Args::from_slice }
+    } else {
+        quote! { wasmlanche::borsh::from_slice }
+    };
 
     let external_call = quote! {
         mod private {
             use super::*;
-            #[derive(wasmlanche::borsh::BorshDeserialize)]
-            #[borsh(crate = "wasmlanche::borsh")]
+            #args_derive
             struct Args {
                 ctx: #context_type,
                 #(#other_inputs),*
             }
 
+            #decode_args
+
             #[link(wasm_import_module = "contract")]
             extern "C" {
                 #[link_name = "set_call_result"]
@@ -62,11 +147,11 @@ pub fn impl_public(public_fn: PublicFn) -> Result<TokenStream, Error> {
                     #[cfg(not(target_arch = "wasm32"))]
                     let args_slice = &args;
 
-                    let args: Args = wasmlanche::borsh::from_slice(args_slice).expect("error fetching serialized args");
+                    let args: Args = #from_slice(args_slice).expect("error fetching serialized args");
 
                     let Args { mut ctx, #(#args_names),* } = args;
 
-                    let result = super::#name(&mut ctx, #(#args_names_2),*);
+                    let result = super::#name(&mut ctx, #(#call_args),*);
                     wasmlanche::borsh::to_vec(&result).expect("error serializing result")
                 };
 
@@ -107,6 +192,9 @@ pub struct PublicFn {
     attrs: Vec<syn::Attribute>,
     vis: Visibility,
     sig: PublicFnSignature,
+    /// The value used for each of `sig.other_inputs` when the input ends before it.
+    /// Only the trailing `Option` and `#[default(..)]` parameters have one.
+    trailing_defaults: Vec<Option<Expr>>,
     block: Box<Block>,
 }
 
@@ -147,7 +235,10 @@ impl Parse for PublicFn {
             (None, context_input) => context_input,
         };
 
-        let other_inputs = map_other_inputs(inputs);
+        let other_inputs = map_other_inputs(inputs).and_then(|(inputs, explicit_defaults)| {
+            let defaults = trailing_defaults(&inputs, explicit_defaults)?;
+            Ok((inputs, defaults))
+        });
 
         let (_context_input, other_inputs) = match (context_input, other_inputs) {
             (Err(mut vis_and_first), Err(rest)) => {
@@ -158,6 +249,8 @@ impl Parse for PublicFn {
             (Ok(context_input), Ok(other_inputs)) => Ok((context_input, other_inputs)),
         }?;
 
+        let (other_inputs, trailing_defaults) = other_inputs;
+
         let fn_token = sig.fn_token;
 
         let sig = PublicFnSignature {
@@ -173,6 +266,7 @@ impl Parse for PublicFn {
             attrs,
             vis,
             sig,
+            trailing_defaults,
             block,
         })
     }
@@ -185,6 +279,7 @@ impl From<PublicFn> for ItemFn {
             vis,
             sig,
             block,
+            ..
         } = public_fn;
 
         Self {
@@ -283,11 +378,17 @@ impl PublicFn {
 
         let name = &sig.ident;
         let other_inputs = sig.other_inputs.iter().collect::<Vec<_>>();
-        let args_names = sig
+        let args_fields = sig.other_inputs.iter().map(owned_input);
+        let args_values = sig
             .other_inputs
             .iter()
-            .map(|PatType { pat: name, .. }| quote! {#name})
-            .collect::<Vec<_>>();
+            .map(|PatType { pat: name, ty, .. }| match Borrowed::of(ty) {
+                Some(Borrowed::Str) => quote! { #name: ::std::borrow::ToOwned::to_owned(#name) },
+                Some(Borrowed::Cow) => {
+                    quote! { #name: ::std::borrow::Cow::Owned(::std::borrow::Cow::into_owned(#name)) }
+                }
+                None => quote! { #name },
+            });
 
         let context_type = type_from_reference(&sig.user_defined_context_type);
 
@@ -301,12 +402,12 @@ impl PublicFn {
                     #[borsh(crate = "wasmlanche::borsh")]
                     struct Args {
                         ctx: #context_type,
-                        #(#other_inputs),*
+                        #(#args_fields),*
                     }
 
                     Args {
                         ctx: wasmlanche::Context::new(),
-                        #(#args_names),*
+                        #(#args_values),*
                     }
                 };
 
@@ -365,8 +466,13 @@ where
     )
 }
 
-fn map_other_inputs(inputs: impl Iterator<Item = FnArg>) -> Result<CommaSeparated<PatType>, Error> {
+type ExplicitDefaults = Vec<Option<Expr>>;
+
+fn map_other_inputs(
+    inputs: impl Iterator<Item = FnArg>,
+) -> Result<(CommaSeparated<PatType>, ExplicitDefaults), Error> {
     let mut other_inputs = CommaSeparated::new();
+    let mut explicit_defaults = Vec::new();
 
     for input in inputs {
         let FnArg::Typed(pat_type) = input else {
@@ -376,7 +482,9 @@ fn map_other_inputs(inputs: impl Iterator<Item = FnArg>) -> Result<CommaSeparate
             ));
         };
 
-        let PatType { pat, ty, .. } = pat_type;
+        let PatType { attrs, pat, ty, .. } = pat_type;
+
+        explicit_defaults.push(default_attr(&attrs)?);
 
         let pat_clone = pat.clone();
         match &*pat {
@@ -405,7 +513,130 @@ fn map_other_inputs(inputs: impl Iterator<Item = FnArg>) -> Result<CommaSeparate
         });
     }
 
-    Ok(other_inputs)
+    Ok((other_inputs, explicit_defaults))
+}
+
+/// Parses the expression out of a `#[default(expr)]` parameter attribute.
+fn default_attr(attrs: &[Attribute]) -> Result<Option<Expr>, Error> {
+    let mut defaults = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident(DEFAULT_ATTR));
+
+    let Some(attr) = defaults.next() else {
+        return Ok(None);
+    };
+
+    if let Some(duplicate) = defaults.next() {
+        return Err(Error::new(
+            duplicate.span(),
+            "a parameter can only have one `#[default(..)]`",
+        ));
+    }
+
+    attr.parse_args().map(Some)
+}
+
+/// Resolves the default of each parameter in the trailing run of `Option`
+/// and `#[default(..)]` parameters, which may be left out by callers.
+fn trailing_defaults(
+    other_inputs: &CommaSeparated<PatType>,
+    explicit_defaults: ExplicitDefaults,
+) -> Result<Vec<Option<Expr>>, Error> {
+    let mut trailing = true;
+
+    let mut defaults = other_inputs
+        .iter()
+        .zip(explicit_defaults)
+        .rev()
+        .map(|(PatType { ty, .. }, explicit)| match explicit {
+            Some(default) if trailing => Ok(Some(default)),
+            Some(default) => Err(Error::new(
+                default.span(),
+                "`#[default(..)]` parameters must come after every required parameter",
+            )),
+            None if trailing && is_option(ty) => {
+                Ok(Some(parse_quote! { ::core::option::Option::None }))
+            }
+            None => {
+                trailing = false;
+                Ok(None)
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    defaults.reverse();
+
+    Ok(defaults)
+}
+
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return false;
+    };
+
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Option")
+}
+
+/// A parameter the function borrows, which is decoded into an owned value first.
+enum Borrowed {
+    /// `&str`, decoded as a `String`.
+    Str,
+    /// `Cow<'_, T>`, decoded as a `Cow<'static, T>`.
+    Cow,
+}
+
+impl Borrowed {
+    fn of(ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Reference(TypeReference {
+                mutability: None,
+                elem,
+                ..
+            }) => {
+                let Type::Path(TypePath { qself: None, path }) = &**elem else {
+                    return None;
+                };
+                path.is_ident("str").then_some(Self::Str)
+            }
+            Type::Path(TypePath { qself: None, path }) => {
+                let segment = path.segments.last()?;
+                let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                    return None;
+                };
+
+                let is_cow = segment.ident == "Cow"
+                    && matches!(args.args.first(), Some(GenericArgument::Lifetime(_)));
+                is_cow.then_some(Self::Cow)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The field of the generated `Args` holding `input`,
+/// which owns the data of borrowed parameters.
+fn owned_input(input: &PatType) -> PatType {
+    let mut input = input.clone();
+
+    match Borrowed::of(&input.ty) {
+        Some(Borrowed::Str) => input.ty = parse_quote! { ::std::string::String },
+        Some(Borrowed::Cow) => {
+            if let Type::Path(TypePath { path, .. }) = &mut *input.ty {
+                if let Some(PathArguments::AngleBracketed(args)) = path
+                    .segments
+                    .last_mut()
+                    .map(|segment| &mut segment.arguments)
+                {
+                    args.args[0] = GenericArgument::Lifetime(parse_quote! { 'static });
+                }
+            }
+        }
+        None => {}
+    }
+
+    input
 }
 
 /// Returns whether the type_path represents a mutable context ref type.
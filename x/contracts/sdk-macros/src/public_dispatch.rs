@@ -0,0 +1,243 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{
+    spanned::Spanned, Error, Fields, FnArg, Ident, ImplItem, ImplItemFn, Item, ItemEnum, ItemMod,
+    Type, Variant,
+};
+
+const DISPATCH_FN_NAME: &str = "execute";
+
+pub fn impl_public_dispatch(module: ItemMod) -> Result<TokenStream, Error> {
+    let Some((_, items)) = &module.content else {
+        return Err(Error::new(
+            module.span(),
+            "`#[public_dispatch]` must be applied to an inline module",
+        ));
+    };
+
+    let action = find_action_enum(&module, items)?;
+    let enum_name = &action.ident;
+    let handlers = find_handlers(items, enum_name);
+
+    let mut arms = Vec::with_capacity(action.variants.len());
+    let mut exports = Vec::with_capacity(action.variants.len());
+    let mut errors: Option<Error> = None;
+
+    for variant in &action.variants {
+        match dispatch_variant(enum_name, variant, &handlers) {
+            Ok((arm, export)) => {
+                arms.push(arm);
+                exports.push(export);
+            }
+            Err(err) => match &mut errors {
+                Some(errors) => errors.combine(err),
+                None => errors = Some(err),
+            },
+        }
+    }
+
+    if let Some(errors) = errors {
+        return Err(errors);
+    }
+
+    let dispatch_fn_name = format_ident!("{DISPATCH_FN_NAME}");
+
+    let generated = quote! {
+        impl #enum_name {
+            /// Runs the handler for this action and returns its borsh-encoded result.
+            pub fn dispatch(self, context: &mut wasmlanche::Context) -> wasmlanche::DispatchResult {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+
+        #[wasmlanche::public]
+        pub fn #dispatch_fn_name(
+            context: &mut wasmlanche::Context,
+            action: #enum_name,
+        ) -> wasmlanche::DispatchResult {
+            action.dispatch(context)
+        }
+
+        #(#exports)*
+    };
+
+    let ItemMod {
+        attrs,
+        vis,
+        unsafety,
+        mod_token,
+        ident,
+        ..
+    } = &module;
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #unsafety #mod_token #ident {
+            #(#items)*
+            #generated
+        }
+    })
+}
+
+fn find_action_enum<'a>(module: &ItemMod, items: &'a [Item]) -> Result<&'a ItemEnum, Error> {
+    let mut enums = items.iter().filter_map(|item| match item {
+        Item::Enum(item_enum) => Some(item_enum),
+        _ => None,
+    });
+
+    let Some(action) = enums.next() else {
+        return Err(Error::new(
+            module.ident.span(),
+            "`#[public_dispatch]` modules must declare exactly one action enum",
+        ));
+    };
+
+    if let Some(other) = enums.next() {
+        return Err(Error::new(
+            other.ident.span(),
+            "`#[public_dispatch]` modules must declare exactly one action enum",
+        ));
+    }
+
+    if !action.generics.params.is_empty() {
+        return Err(Error::new(
+            action.generics.span(),
+            "generic action enums are not supported",
+        ));
+    }
+
+    Ok(action)
+}
+
+/// Collects the associated functions of every inherent `impl` block for the action enum.
+fn find_handlers<'a>(items: &'a [Item], enum_name: &Ident) -> Vec<&'a ImplItemFn> {
+    items
+        .iter()
+        .filter_map(|item| match item {
+            Item::Impl(item_impl) if item_impl.trait_.is_none() => Some(item_impl),
+            _ => None,
+        })
+        .filter(|item_impl| {
+            matches!(&*item_impl.self_ty, Type::Path(path) if path.path.is_ident(enum_name))
+        })
+        .flat_map(|item_impl| &item_impl.items)
+        .filter_map(|item| match item {
+            ImplItem::Fn(item_fn) => Some(item_fn),
+            _ => None,
+        })
+        .collect()
+}
+
+fn dispatch_variant(
+    enum_name: &Ident,
+    variant: &Variant,
+    handlers: &[&ImplItemFn],
+) -> Result<(TokenStream, TokenStream), Error> {
+    let variant_name = &variant.ident;
+    let handler_name = Ident::new(&to_snake_case(&variant_name.to_string()), Span::call_site());
+
+    if handler_name == DISPATCH_FN_NAME {
+        return Err(Error::new(
+            variant_name.span(),
+            format!("variant `{variant_name}` conflicts with the `{DISPATCH_FN_NAME}` dispatcher"),
+        ));
+    }
+
+    let Some(handler) = handlers.iter().find(|f| f.sig.ident == handler_name) else {
+        return Err(Error::new(
+            variant_name.span(),
+            format!(
+                "missing handler for variant `{variant_name}`: expected `fn {handler_name}(context: &mut Context, ..)`"
+            ),
+        ));
+    };
+
+    let mut inputs = handler.sig.inputs.iter();
+
+    let context_type = match inputs.next() {
+        Some(FnArg::Typed(context)) => &context.ty,
+        Some(FnArg::Receiver(receiver)) => {
+            return Err(Error::new(
+                receiver.span(),
+                "action handlers cannot take `self`",
+            ))
+        }
+        None => {
+            return Err(Error::new(
+                handler.sig.paren_token.span.join(),
+                "missing context argument",
+            ))
+        }
+    };
+
+    let param_types = inputs
+        .map(|input| match input {
+            FnArg::Typed(pat_type) => Ok(&pat_type.ty),
+            FnArg::Receiver(receiver) => Err(Error::new(
+                receiver.span(),
+                "action handlers cannot take `self`",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if param_types.len() != variant.fields.len() {
+        return Err(Error::new(
+            handler.sig.span(),
+            format!(
+                "handler `{handler_name}` takes {} argument(s) after the context but variant `{variant_name}` has {} field(s)",
+                param_types.len(),
+                variant.fields.len(),
+            ),
+        ));
+    }
+
+    let bindings = (0..param_types.len())
+        .map(|i| format_ident!("arg{i}"))
+        .collect::<Vec<_>>();
+
+    let pattern = match &variant.fields {
+        Fields::Unit => quote! { Self::#variant_name },
+        Fields::Unnamed(_) => quote! { Self::#variant_name(#(#bindings),*) },
+        Fields::Named(fields) => {
+            let names = fields.named.iter().map(|field| &field.ident);
+            quote! { Self::#variant_name { #(#names: #bindings),* } }
+        }
+    };
+
+    let arm = quote_spanned! {variant_name.span()=>
+        #pattern => wasmlanche::DispatchResult::new(&Self::#handler_name(context, #(#bindings),*)),
+    };
+
+    let output = &handler.sig.output;
+
+    let export = quote! {
+        #[wasmlanche::public]
+        pub fn #handler_name(context: #context_type, #(#bindings: #param_types),*) #output {
+            #enum_name::#handler_name(context, #(#bindings),*)
+        }
+    };
+
+    Ok((arm, export))
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+
+    for (i, c) in name.char_indices() {
+        if c.is_uppercase() {
+            if i != 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+
+    snake
+}
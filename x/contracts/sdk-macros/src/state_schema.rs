@@ -25,12 +25,16 @@ pub fn impl_state_schema(
                 value_type,
             },
         )| {
-            let i = u8::try_from(i).map_err(|_| {
-                Error::new(
-                    key_type_name.span(),
-                    "Cannot exceed `u8::MAX + 1` keys in a state-schema",
-                )
-            })?;
+            // the last prefix is reserved for `wasmlanche::pause`
+            let i = u8::try_from(i)
+                .ok()
+                .filter(|&i| i < u8::MAX)
+                .ok_or_else(|| {
+                    Error::new(
+                        key_type_name.span(),
+                        "Cannot exceed `u8::MAX` keys in a state-schema, prefix `0xff` is reserved for the pause flag",
+                    )
+                })?;
 
             token_stream.extend(Some(quote! {
                 #(#key_comments)*
@@ -0,0 +1,62 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, spanned::Spanned, Error, FnArg, ItemFn, Pat, PatIdent, ReturnType, Type};
+
+pub fn impl_when_not_paused(mut item: ItemFn) -> Result<TokenStream, Error> {
+    let context = match item.sig.inputs.first() {
+        Some(FnArg::Typed(pat_type)) => match &*pat_type.pat {
+            Pat::Ident(PatIdent { ident, .. }) => ident.clone(),
+            pat => {
+                return Err(Error::new(
+                    pat.span(),
+                    "`#[when_not_paused]` needs the context argument to be named, or to be applied below `#[public]`",
+                ))
+            }
+        },
+        Some(FnArg::Receiver(receiver)) => {
+            return Err(Error::new(
+                receiver.span(),
+                "`#[when_not_paused]` cannot be applied to methods",
+            ))
+        }
+        None => {
+            return Err(Error::new(
+                item.sig.paren_token.span.join(),
+                "missing context argument",
+            ))
+        }
+    };
+
+    if !returns_result(&item.sig.output) {
+        return Err(Error::new(
+            item.sig.output.span(),
+            "`#[when_not_paused]` functions must return a `Result` whose error converts from `wasmlanche::Revert`",
+        ));
+    }
+
+    item.block.stmts.insert(
+        0,
+        parse_quote! {
+            wasmlanche::ensure!(!#context.is_paused(), wasmlanche::pause::PAUSED);
+        },
+    );
+
+    Ok(quote! { #item })
+}
+
+fn returns_result(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let Type::Path(type_path) = &**ty else {
+        return false;
+    };
+    type_path
+        .path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Result")
+}
@@ -0,0 +1,14 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use wasmlanche::public;
+
+#[allow(unused_imports)]
+use wasmlanche::Context;
+
+#[public]
+pub fn test(_: &mut Context, #[default(1)] fee: u64, amount: u64) -> u64 {
+    fee + amount
+}
+
+fn main() {}
@@ -0,0 +1,27 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use wasmlanche::public_dispatch;
+
+#[public_dispatch]
+mod actions {
+    use wasmlanche::{
+        borsh::{BorshDeserialize, BorshSerialize},
+        Context,
+    };
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    #[borsh(crate = "wasmlanche::borsh")]
+    pub enum Action {
+        Increment(u64),
+        Reset,
+    }
+
+    impl Action {
+        fn increment(_: &mut Context, amount: u64) -> u64 {
+            amount + 1
+        }
+    }
+}
+
+fn main() {}
@@ -4,12 +4,13 @@
 use seq_macro::seq;
 use wasmlanche::state_schema;
 
-seq!(N in 0..=255 {
+// the 256th key would use the prefix reserved for the pause flag
+seq!(N in 0..255 {
     state_schema! {
         #(
             Variant~N => u8,
         )*
-        Variant256 => u8,
+        Paused => bool,
     }
 });
 
@@ -0,0 +1,14 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use sdk_macros::when_not_paused;
+
+#[allow(unused_imports)]
+use wasmlanche::Context;
+
+#[when_not_paused]
+fn transfer(_context: &mut Context, amount: u64) -> u64 {
+    amount
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use sdk_macros::when_not_paused;
+
+#[allow(unused_imports)]
+use wasmlanche::{Context, Revert};
+
+#[when_not_paused]
+fn transfer(_: &mut Context, amount: u64) -> Result<u64, Revert> {
+    Ok(amount)
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use std::borrow::Cow;
+use wasmlanche::{public, Context};
+
+#[public]
+pub fn name_len(_: &mut Context, name: &str) -> u64 {
+    name.len() as u64
+}
+
+#[public]
+pub fn greet(_: &mut Context, greeting: Cow<'_, str>, name: &str) -> String {
+    format!("{greeting}, {name}")
+}
+
+#[public]
+pub fn checksum(_: &mut Context, data: Cow<'_, [u8]>, #[default(0)] seed: u8) -> u8 {
+    data.iter().fold(seed, |acc, byte| acc ^ byte)
+}
+
+fn main() {}
@@ -0,0 +1,23 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use wasmlanche::{public, Address, Context};
+
+#[public]
+pub fn optional(_: &mut Context, to: Address, amount: Option<u64>) -> Option<u64> {
+    let _ = to;
+    amount
+}
+
+#[public]
+pub fn defaulted(_: &mut Context, amount: u64, #[default(10)] fee: u64, memo: Option<u8>) -> u64 {
+    let _ = memo;
+    amount + fee
+}
+
+#[public(strict_args)]
+pub fn strict(_: &mut Context, amount: Option<u64>) -> Option<u64> {
+    amount
+}
+
+fn main() {}
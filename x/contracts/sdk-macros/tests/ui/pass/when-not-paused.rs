@@ -0,0 +1,20 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use wasmlanche::{public, when_not_paused, Context, Revert};
+
+#[public]
+#[when_not_paused]
+pub fn transfer(_: &mut Context, amount: u64) -> Result<u64, Revert> {
+    Ok(amount)
+}
+
+#[when_not_paused]
+fn helper(ctx: &mut Context) -> Result<(), Revert> {
+    let _ = ctx.actor();
+    Ok(())
+}
+
+fn main() {
+    let _ = helper;
+}
@@ -0,0 +1,237 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Bech32 encoding for the `addr` imports, so contracts bridging to other ecosystems
+//! don't have to carry their own implementation.
+//!
+//! Errors are the discriminants of wasmlanche's `Bech32Error`.
+
+use bech32::{
+    primitives::{
+        decode::{
+            CharError, CheckedHrpstring, CheckedHrpstringError, ChecksumError,
+            UncheckedHrpstringError,
+        },
+        hrp,
+    },
+    Bech32, EncodeError, Hrp,
+};
+
+/// Fuel charged for every byte of arguments passed to the `addr` imports.
+pub const BECH32_GAS_PER_BYTE: u64 = 10;
+
+const INVALID_HRP: u8 = 0;
+const INVALID_CHARACTER: u8 = 1;
+const MIXED_CASE: u8 = 2;
+const INVALID_CHECKSUM: u8 = 3;
+const WRONG_HRP: u8 = 4;
+const INVALID_LENGTH: u8 = 5;
+
+/// Encodes `data` as a BIP-173 bech32 string with the human-readable part `hrp`.
+pub(crate) fn encode(hrp: &str, data: &[u8]) -> Result<String, u8> {
+    let hrp = parse_hrp(hrp)?;
+
+    bech32::encode::<Bech32>(hrp, data).map_err(|err| match err {
+        EncodeError::TooLong(_) => INVALID_LENGTH,
+        _ => INVALID_CHARACTER,
+    })
+}
+
+/// Decodes a BIP-173 bech32 string, which must have the human-readable part `hrp`.
+pub(crate) fn decode(hrp: &str, encoded: &str) -> Result<Vec<u8>, u8> {
+    let hrp = parse_hrp(hrp)?;
+    let checked = CheckedHrpstring::new::<Bech32>(encoded).map_err(|err| match err {
+        CheckedHrpstringError::Parse(UncheckedHrpstringError::Hrp(err)) => hrp_error(&err),
+        CheckedHrpstringError::Parse(UncheckedHrpstringError::Char(CharError::MixedCase)) => {
+            MIXED_CASE
+        }
+        CheckedHrpstringError::Checksum(ChecksumError::InvalidResidue) => INVALID_CHECKSUM,
+        CheckedHrpstringError::Checksum(_) => INVALID_LENGTH,
+        _ => INVALID_CHARACTER,
+    })?;
+
+    if checked.hrp() != hrp {
+        return Err(WRONG_HRP);
+    }
+
+    Ok(checked.byte_iter().collect())
+}
+
+fn parse_hrp(hrp: &str) -> Result<Hrp, u8> {
+    Hrp::parse(hrp).map_err(|err| hrp_error(&err))
+}
+
+fn hrp_error(err: &hrp::Error) -> u8 {
+    match err {
+        hrp::Error::MixedCase => MIXED_CASE,
+        _ => INVALID_HRP,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // BIP-173 test vectors, and a 20 byte cosmos address of the bytes 0 to 19
+    const VALID: &[(&str, &str, &[u8])] = &[
+        ("a", "A12UEL5L", &[]),
+        ("a", "a12uel5l", &[]),
+        (
+            "abcdef",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            &[
+                0x00, 0x44, 0x32, 0x14, 0xc7, 0x42, 0x54, 0xb6, 0x35, 0xcf, 0x84, 0x65, 0x3a, 0x56,
+                0xd7, 0xc6, 0x75, 0xbe, 0x77, 0xdf,
+            ],
+        ),
+        (
+            "cosmos",
+            "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e",
+            &[
+                0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19,
+            ],
+        ),
+    ];
+
+    #[test]
+    fn known_answers() {
+        for (hrp, encoded, data) in VALID {
+            assert_eq!(decode(hrp, encoded).as_deref(), Ok(*data), "{encoded}");
+            assert_eq!(encode(hrp, data), Ok(encoded.to_lowercase()), "{encoded}");
+        }
+    }
+
+    #[test]
+    fn invalid_strings() {
+        let cases = [
+            ("a", "A1G7SGD8", INVALID_CHECKSUM),
+            ("a", "A12uEL5L", MIXED_CASE),
+            ("a", "1nwldj5", INVALID_HRP),
+            ("x", "x1b4n0q5v", INVALID_CHARACTER),
+            ("li", "li1dgmt3", INVALID_LENGTH),
+            (
+                "osmo",
+                "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e",
+                WRONG_HRP,
+            ),
+            (
+                "cosmos",
+                "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363f",
+                INVALID_CHECKSUM,
+            ),
+        ];
+
+        for (hrp, encoded, expected) in cases {
+            assert_eq!(decode(hrp, encoded), Err(expected), "{encoded}");
+        }
+    }
+
+    #[test]
+    fn invalid_hrps() {
+        assert_eq!(encode("", &[1]), Err(INVALID_HRP));
+        assert_eq!(encode("Cosmos", &[1]), Err(MIXED_CASE));
+        assert_eq!(encode("cosmos", &[0; 700]), Err(INVALID_LENGTH));
+    }
+
+    #[test]
+    fn decode_never_panics() {
+        const ALPHABET: &[char] = &[
+            'q', 'p', 'z', 'r', 'y', '9', 'x', '8', 'Q', 'P', '1', 'b', 'o', ' ', '\u{0}', 'é',
+        ];
+
+        // xorshift, so the inputs are the same on every run
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+
+        for _ in 0..10_000 {
+            let len = (next() % 100) as usize;
+            let encoded: String = (0..len)
+                .map(|_| ALPHABET[(next() % ALPHABET.len() as u64) as usize])
+                .collect();
+            let _ = decode("q", &encoded);
+
+            let data: Vec<u8> = (0..len % 40).map(|_| next() as u8).collect();
+            let encoded = encode("q", &data).unwrap();
+            assert_eq!(decode("q", &encoded), Ok(data));
+        }
+    }
+
+    // `encode` and `decode` pass their params on to the `addr` imports and return the result
+    const BECH32: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "addr" "bech32_encode" (func $encode (param i32 i32) (result i32)))
+            (import "addr" "bech32_decode" (func $decode (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $last_len (mut i32) (i32.const 0))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.set $last_len (local.get $len))
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "encode") (param $ptr i32)
+                (local $result i32)
+                (local.set $result (call $encode (local.get $ptr) (global.get $last_len)))
+                (call $set_call_result (local.get $result) (global.get $last_len)))
+            (func (export "decode") (param $ptr i32)
+                (local $result i32)
+                (local.set $result (call $decode (local.get $ptr) (global.get $last_len)))
+                (call $set_call_result (local.get $result) (global.get $last_len))))
+    "#;
+
+    #[test]
+    fn contracts_round_trip_addresses() {
+        let simulator = crate::Simulator::new();
+        let data: Vec<u8> = (0..20).collect();
+
+        let encoded: Result<String, u8> = simulator
+            .execute_typed(BECH32.as_bytes(), "encode", &("cosmos", &data), 1_000_000)
+            .unwrap();
+        let encoded = encoded.unwrap();
+        assert_eq!(encoded, "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e");
+
+        let decoded: Result<Vec<u8>, u8> = simulator
+            .execute_typed(
+                BECH32.as_bytes(),
+                "decode",
+                &("cosmos", &encoded),
+                1_000_000,
+            )
+            .unwrap();
+        assert_eq!(decoded, Ok(data));
+
+        let decoded: Result<Vec<u8>, u8> = simulator
+            .execute_typed(BECH32.as_bytes(), "decode", &("osmo", &encoded), 1_000_000)
+            .unwrap();
+        assert_eq!(decoded, Err(WRONG_HRP));
+    }
+
+    #[test]
+    fn gas_is_charged_per_byte() {
+        let simulator = crate::Simulator::new();
+        let gas_used = |data: &[u8]| {
+            let _: Result<String, u8> = simulator
+                .execute_typed(BECH32.as_bytes(), "encode", &("a", data), 1_000_000)
+                .unwrap();
+            simulator.last_gas_used()
+        };
+
+        let (short, long) = (gas_used(&[0; 1]), gas_used(&[0; 41]));
+        assert!(long - short >= 40 * BECH32_GAS_PER_BYTE, "{short} {long}");
+
+        let err = simulator
+            .execute_typed::<_, Result<String, u8>>(
+                BECH32.as_bytes(),
+                "encode",
+                &("a", [0; 41]),
+                100,
+            )
+            .unwrap_err();
+        assert!(matches!(err, crate::SimulatorError::OutOfGas), "{err}");
+    }
+}
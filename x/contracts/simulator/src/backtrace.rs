@@ -0,0 +1,172 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Readable backtraces for contracts that trap.
+//!
+//! Frames are named from the module's `name` section, demangled, and show the file and line they
+//! were at when the module has DWARF debug info and the `debug-info` feature is enabled.
+//! Modules without a `name` section fall back to function indexes.
+
+use std::fmt::Write as _;
+use wasmtime::{FrameInfo, WasmBacktrace};
+
+/// Frames of the standard library's panic machinery, which are left off the top of backtraces
+/// so that they start at the function that panicked.
+const PANIC_FRAMES: &[&str] = &[
+    "core::panicking::",
+    "core::panic::",
+    "core::result::unwrap_failed",
+    "core::option::unwrap_failed",
+    "core::option::expect_failed",
+    "std::panicking::",
+    "std::panic::",
+    "std::sys::",
+    "std::rt::",
+    "__rust_",
+    "rust_begin_unwind",
+    "rust_panic",
+];
+
+/// Renders `err` with the backtrace of the contract frames it was raised in, if it has one.
+pub(crate) fn render(err: &wasmtime::Error) -> String {
+    let reason = err.root_cause().to_string();
+
+    match err.downcast_ref::<WasmBacktrace>() {
+        Some(backtrace) if !backtrace.frames().is_empty() => {
+            format!("{reason}\n{}", render_frames(backtrace.frames()))
+        }
+        _ => reason,
+    }
+}
+
+fn render_frames(frames: &[FrameInfo]) -> String {
+    let start = frames
+        .iter()
+        .position(|frame| !is_panic_frame(&function_name(frame)))
+        .unwrap_or(0);
+
+    let mut rendered = String::from("wasm backtrace:");
+    for (i, frame) in frames[start..].iter().enumerate() {
+        let _ = write!(rendered, "\n  {i:>3}: {}", function_name(frame));
+        for symbol in frame.symbols() {
+            let Some(file) = symbol.file() else {
+                continue;
+            };
+            let _ = write!(rendered, "\n           at {file}");
+            if let Some(line) = symbol.line() {
+                let _ = write!(rendered, ":{line}");
+            }
+            if let Some(column) = symbol.column() {
+                let _ = write!(rendered, ":{column}");
+            }
+        }
+    }
+    rendered
+}
+
+fn function_name(frame: &FrameInfo) -> String {
+    match frame.func_name() {
+        Some(name) => demangle(name),
+        None => format!("<wasm function {}>", frame.func_index()),
+    }
+}
+
+/// Demangles Rust symbol names without their hash, leaving other names as they are.
+fn demangle(name: &str) -> String {
+    format!("{:#}", rustc_demangle::demangle(name))
+}
+
+fn is_panic_frame(name: &str) -> bool {
+    PANIC_FRAMES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Simulator, SimulatorError};
+
+    // `run` calls `outer`, which calls `inner`, which traps
+    // `core::panicking::panic` stands in for the standard library's panic machinery
+    const NESTED: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 0)
+            (func $_ZN4core9panicking5panic17h0123456789abcdefE unreachable)
+            (func $_ZN7counter5inner17h0123456789abcdefE call $_ZN4core9panicking5panic17h0123456789abcdefE)
+            (func $outer call $_ZN7counter5inner17h0123456789abcdefE)
+            (func (export "run") (param i32) call $outer))
+    "#;
+
+    fn trap(wat: &str) -> String {
+        let simulator = Simulator::new();
+        let module = wat::parse_str(wat).unwrap();
+        match simulator.execute_wasm(&module, "run", &[], 1_000) {
+            Err(SimulatorError::ContractExecution(message)) => message,
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn names_frames() {
+        assert_eq!(
+            trap(NESTED),
+            [
+                "wasm trap: wasm `unreachable` instruction executed",
+                "wasm backtrace:",
+                "    0: counter::inner",
+                "    1: outer",
+                "    2: <wasm function 4>",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_indexes() {
+        // `wat` names functions after their identifiers, so these have none
+        let nameless = r#"
+            (module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func unreachable)
+                (func (export "run") (param i32) call 1))
+        "#;
+
+        let message = trap(nameless);
+        assert!(
+            message
+                .ends_with("wasm backtrace:\n    0: <wasm function 1>\n    1: <wasm function 2>"),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn keeps_host_errors() {
+        let message = trap(
+            r#"
+            (module
+                (import "state" "get" (func $get (param i32 i32) (result i32)))
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "run") (param i32)
+                    (drop (call $get (i32.const 0) (i32.const 1)))))
+        "#,
+        );
+        let (reason, backtrace) = message.split_once('\n').unwrap();
+        assert!(
+            !reason.is_empty() && !reason.contains("backtrace"),
+            "{message}"
+        );
+        assert!(backtrace.contains("<wasm function 2>"), "{message}");
+    }
+
+    #[test]
+    fn recognizes_panic_frames() {
+        assert!(is_panic_frame(&demangle(
+            "_ZN4core9panicking5panic17h0123456789abcdefE"
+        )));
+        assert!(is_panic_frame("__rust_start_panic"));
+        assert!(!is_panic_frame("counter::inner"));
+        assert_eq!(demangle("outer"), "outer");
+    }
+}
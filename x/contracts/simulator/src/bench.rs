@@ -0,0 +1,199 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Repeated execution of a single method for performance measurements.
+
+use crate::{Simulator, SimulatorError};
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BenchOptions {
+    /// Iterations run before measuring, which are not part of the report.
+    pub warmup: usize,
+    /// Restores the state from before the first iteration after every iteration,
+    /// so that every run sees the same pre-state.
+    /// Without it, state changes made by earlier runs may change the cost of later ones.
+    pub reset_state_each_iter: bool,
+}
+
+/// Distribution of a measurement across the iterations of a [`Simulator::bench`] run.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats<T> {
+    pub min: T,
+    pub p50: T,
+    pub p95: T,
+    pub max: T,
+    pub mean: T,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BenchReport {
+    pub iterations: usize,
+    pub latency: Stats<Duration>,
+    pub gas: Stats<u64>,
+}
+
+impl Simulator {
+    /// Calls `method` `iterations` times after `options.warmup` unmeasured calls,
+    /// reporting the wall time and gas of each measured call.
+    /// The state is restored to what it was before the run if `options.reset_state_each_iter` is set.
+    ///
+    /// Stops at the first failing call and returns its error.
+    pub fn bench(
+        &self,
+        code: &[u8],
+        method: &str,
+        params: &[u8],
+        gas: u64,
+        iterations: usize,
+        options: BenchOptions,
+    ) -> Result<BenchReport, SimulatorError> {
+        let snapshot = options.reset_state_each_iter.then(|| self.snapshot());
+
+        let mut latencies = Vec::with_capacity(iterations);
+        let mut gas_used = Vec::with_capacity(iterations);
+
+        for i in 0..options.warmup + iterations {
+            let start = Instant::now();
+            self.execute_wasm(code, method, params, gas)?;
+            let elapsed = start.elapsed();
+
+            if i >= options.warmup {
+                latencies.push(elapsed);
+                gas_used.push(self.last_gas_used());
+            }
+
+            if let Some(snapshot) = &snapshot {
+                self.restore(snapshot.clone());
+            }
+        }
+
+        Ok(BenchReport {
+            iterations,
+            latency: stats(latencies, |total, n| total / n as u32),
+            gas: stats(gas_used, |total, n| total / n as u64),
+        })
+    }
+}
+
+fn stats<T>(mut samples: Vec<T>, mean: impl FnOnce(T, usize) -> T) -> Stats<T>
+where
+    T: Copy + Default + Ord + std::iter::Sum<T>,
+{
+    if samples.is_empty() {
+        return Stats::default();
+    }
+
+    samples.sort_unstable();
+
+    let n = samples.len();
+    let percentile = |p: usize| samples[(n - 1) * p / 100];
+
+    Stats {
+        min: samples[0],
+        p50: percentile(50),
+        p95: percentile(95),
+        max: samples[n - 1],
+        mean: mean(samples.iter().copied().sum(), n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // counts its calls in state under `count`
+    const COUNTER: &str = r#"
+        (module
+            (import "state" "get" (func $get (param i32 i32) (result i32)))
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `GetArgs { key: b"count" }`
+            (data (i32.const 0) "\05\00\00\00count")
+            ;; borsh `vec![(b"count", [n])]`, `n` is written at offset 49
+            (data (i32.const 32) "\01\00\00\00\05\00\00\00count\01\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "increment") (param i32)
+                (local $value i32)
+                (local.set $value (call $get (i32.const 0) (i32.const 9)))
+                (i32.store8 (i32.const 49)
+                    (if (result i32) (local.get $value)
+                        (then (i32.add (i32.load8_u (local.get $value)) (i32.const 1)))
+                        (else (i32.const 1))))
+                (call $put (i32.const 32) (i32.const 18))))
+    "#;
+
+    fn count(simulator: &Simulator) -> Option<u8> {
+        let state = simulator.get_state();
+        let state = state.read().unwrap();
+        state.get_value(b"count").map(|value| value[0])
+    }
+
+    #[test]
+    fn report_is_consistent() {
+        let simulator = Simulator::new();
+        let options = BenchOptions {
+            warmup: 2,
+            reset_state_each_iter: true,
+        };
+
+        let report = simulator
+            .bench(COUNTER.as_bytes(), "increment", &[], 100_000, 50, options)
+            .unwrap();
+
+        assert_eq!(report.iterations, 50);
+
+        let Stats {
+            min, p50, p95, max, ..
+        } = report.latency;
+        assert!(min <= p50 && p50 <= p95 && p95 <= max);
+
+        // every iteration starts from the same state, so they all cost the same
+        assert!(report.gas.min > 0);
+        assert_eq!(report.gas.min, report.gas.max);
+        assert_eq!(report.gas.mean, report.gas.min);
+    }
+
+    #[test]
+    fn reset_mode_isolates_state() {
+        let simulator = Simulator::new();
+        let reset = BenchOptions {
+            warmup: 0,
+            reset_state_each_iter: true,
+        };
+
+        simulator
+            .bench(COUNTER.as_bytes(), "increment", &[], 100_000, 5, reset)
+            .unwrap();
+        assert_eq!(count(&simulator), None);
+
+        simulator
+            .bench(
+                COUNTER.as_bytes(),
+                "increment",
+                &[],
+                100_000,
+                5,
+                BenchOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(count(&simulator), Some(5));
+    }
+
+    #[test]
+    fn stats_of_known_samples() {
+        let samples: Vec<u64> = (1..=100).rev().collect();
+        let stats = stats(samples, |total, n| total / n as u64);
+
+        assert_eq!(
+            stats,
+            Stats {
+                min: 1,
+                p50: 50,
+                p95: 95,
+                max: 100,
+                mean: 50,
+            }
+        );
+    }
+}
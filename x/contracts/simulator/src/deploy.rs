@@ -0,0 +1,326 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Restrictions on who may deploy contracts, see [`Simulator::set_deploy_policy`],
+//! and the addresses contracts are deployed at, see [`Simulator::deploy`].
+
+use crate::{Address, Simulator, SimulatorError, SimulatorState, ADDRESS_LEN};
+use sha2::{Digest, Sha256};
+use std::{collections::HashSet, fmt};
+
+/// Type byte the hypersdk prepends to contract accounts and ed25519 accounts.
+pub const HYPERSDK_TYPE_ID: u8 = 0;
+
+/// Which actors may deploy contracts with [`Simulator::create_contract`].
+/// While the policy has an [admin](DeployPolicy::admin), only the admin may change it with
+/// [`Simulator::set_deploy_policy`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DeployPolicy {
+    /// Anyone may deploy.
+    #[default]
+    Open,
+    /// Only the admin and the `allowed` actors may deploy.
+    AllowList {
+        admin: Address,
+        allowed: HashSet<Address>,
+    },
+    /// Only the admin may deploy.
+    AdminOnly(Address),
+}
+
+impl DeployPolicy {
+    pub fn permits(&self, actor: &Address) -> bool {
+        match self {
+            Self::Open => true,
+            Self::AllowList { admin, allowed } => admin == actor || allowed.contains(actor),
+            Self::AdminOnly(admin) => admin == actor,
+        }
+    }
+
+    /// The only actor that may change the policy, `None` if anyone may.
+    pub fn admin(&self) -> Option<&Address> {
+        match self {
+            Self::Open => None,
+            Self::AllowList { admin, .. } | Self::AdminOnly(admin) => Some(admin),
+        }
+    }
+}
+
+impl fmt::Display for DeployPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open => write!(f, "open"),
+            Self::AllowList { .. } => write!(f, "allow-list"),
+            Self::AdminOnly(_) => write!(f, "admin-only"),
+        }
+    }
+}
+
+impl Simulator {
+    pub fn deploy_policy(&self) -> DeployPolicy {
+        self.deploy_policy.read().unwrap().clone()
+    }
+
+    /// Replaces the deploy policy, returning [`SimulatorError::DeployPolicyForbidden`] for anyone but its admin.
+    /// Only the current policy's [admin](DeployPolicy::admin) may change it, actors it merely lets
+    /// deploy may not.
+    pub fn set_deploy_policy(&self, policy: DeployPolicy) -> Result<(), SimulatorError> {
+        let mut current = self.deploy_policy.write().unwrap();
+        let actor = self.get_actor();
+        if let Some(&admin) = current.admin().filter(|admin| **admin != actor) {
+            return Err(SimulatorError::DeployPolicyForbidden { actor, admin });
+        }
+        *current = policy;
+
+        Ok(())
+    }
+
+    pub(crate) fn check_deploy(&self) -> Result<(), SimulatorError> {
+        check_deploy(&self.deploy_policy.read().unwrap(), self.get_actor())
+    }
+
+    /// Stores `code` at an address derived from the code, `deployer`, and how many contracts
+    /// `deployer` has deployed before, and returns that address.
+    /// Use [`Simulator::create_contract`] to choose the address instead.
+    ///
+    /// Returns [`SimulatorError::DeployForbidden`] if the [`DeployPolicy`] does not let `deployer` deploy.
+    pub fn deploy(&self, deployer: &Address, code: Vec<u8>) -> Result<Address, SimulatorError> {
+        check_deploy(&self.deploy_policy.read().unwrap(), *deployer)?;
+
+        let mut state = self.state.write().unwrap();
+        let nonce = state.next_deploy_nonce(*deployer);
+        let contract = contract_address(deployer, nonce, &code);
+        state.set_contract(contract, code);
+
+        Ok(contract)
+    }
+}
+
+impl SimulatorState {
+    pub fn get_deploy_nonce(&self, deployer: &Address) -> u64 {
+        self.deploy_nonces
+            .get(deployer)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Returns the nonce for the next deploy by `deployer` and increments it.
+    pub(crate) fn next_deploy_nonce(&mut self, deployer: Address) -> u64 {
+        let nonce = self.deploy_nonces.entry(deployer).or_default();
+        let next = *nonce;
+        *nonce += 1;
+        next
+    }
+}
+
+/// `[0] || sha256(sha256(code) || deployer || nonce)`, the [`hypersdk_contract_address`]
+/// with the deployer and its nonce as the salt.
+pub fn contract_address(deployer: &Address, nonce: u64, code: &[u8]) -> Address {
+    let salt = [deployer.as_bytes(), &nonce.to_le_bytes()].concat();
+    hypersdk_contract_address(code, &salt)
+}
+
+/// The address the hypersdk deploys `code` at, `[0] || sha256(contract_id || salt)`
+/// where `contract_id` is `sha256(code)`.
+pub fn hypersdk_contract_address(code: &[u8], salt: &[u8]) -> Address {
+    let contract_id = Sha256::digest(code);
+    let account_id = Sha256::new()
+        .chain_update(contract_id)
+        .chain_update(salt)
+        .finalize();
+
+    let mut address = [0; ADDRESS_LEN];
+    address[0] = HYPERSDK_TYPE_ID;
+    address[1..].copy_from_slice(&account_id);
+    Address::new(address)
+}
+
+fn check_deploy(policy: &DeployPolicy, actor: Address) -> Result<(), SimulatorError> {
+    if policy.permits(&actor) {
+        Ok(())
+    } else {
+        Err(SimulatorError::DeployForbidden {
+            actor,
+            policy: policy.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ADDRESS_LEN;
+
+    const ALICE: Address = Address::new([1; ADDRESS_LEN]);
+    const BOB: Address = Address::new([2; ADDRESS_LEN]);
+    const CAROL: Address = Address::new([4; ADDRESS_LEN]);
+    const CONTRACT: Address = Address::new([3; ADDRESS_LEN]);
+
+    fn deploy_as(simulator: &Simulator, actor: Address) -> Result<(), SimulatorError> {
+        simulator.set_actor(actor);
+        simulator.create_contract(CONTRACT, vec![1, 2, 3])
+    }
+
+    #[test]
+    fn open_by_default() {
+        let simulator = Simulator::new();
+
+        assert_eq!(simulator.deploy_policy(), DeployPolicy::Open);
+        deploy_as(&simulator, ALICE).unwrap();
+        deploy_as(&simulator, BOB).unwrap();
+    }
+
+    #[test]
+    fn allow_list() {
+        let simulator = Simulator::new();
+        simulator
+            .set_deploy_policy(DeployPolicy::AllowList {
+                admin: CAROL,
+                allowed: HashSet::from([ALICE]),
+            })
+            .unwrap();
+
+        deploy_as(&simulator, ALICE).unwrap();
+        deploy_as(&simulator, CAROL).unwrap();
+
+        let err = deploy_as(&simulator, BOB).unwrap_err();
+        let SimulatorError::DeployForbidden { actor, policy } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(*actor, BOB);
+        assert!(matches!(policy, DeployPolicy::AllowList { .. }));
+        assert!(err.to_string().contains("allow-list"), "{err}");
+    }
+
+    #[test]
+    fn admin_only() {
+        let simulator = Simulator::new();
+        simulator
+            .set_deploy_policy(DeployPolicy::AdminOnly(ALICE))
+            .unwrap();
+
+        assert!(matches!(
+            deploy_as(&simulator, BOB),
+            Err(SimulatorError::DeployForbidden { actor, .. }) if actor == BOB
+        ));
+        let state = simulator.get_state();
+        assert!(state.read().unwrap().get_contract(&CONTRACT).is_none());
+
+        // redeploying over an existing contract is subject to the same policy
+        deploy_as(&simulator, ALICE).unwrap();
+        assert!(deploy_as(&simulator, BOB).is_err());
+        deploy_as(&simulator, ALICE).unwrap();
+    }
+
+    #[test]
+    fn only_permitted_actors_change_the_policy() {
+        let simulator = Simulator::new();
+        simulator
+            .set_deploy_policy(DeployPolicy::AdminOnly(ALICE))
+            .unwrap();
+
+        simulator.set_actor(BOB);
+        let err = simulator.set_deploy_policy(DeployPolicy::Open).unwrap_err();
+        assert!(
+            matches!(err, SimulatorError::DeployPolicyForbidden { .. }),
+            "{err}"
+        );
+        assert_eq!(simulator.deploy_policy(), DeployPolicy::AdminOnly(ALICE));
+
+        simulator.set_actor(ALICE);
+        simulator.set_deploy_policy(DeployPolicy::Open).unwrap();
+        deploy_as(&simulator, BOB).unwrap();
+    }
+
+    #[test]
+    fn allow_listed_deployers_cannot_change_the_policy() {
+        let simulator = Simulator::new();
+        let policy = DeployPolicy::AllowList {
+            admin: ALICE,
+            allowed: HashSet::from([BOB]),
+        };
+        simulator.set_deploy_policy(policy.clone()).unwrap();
+
+        simulator.set_actor(BOB);
+        deploy_as(&simulator, BOB).unwrap();
+        let err = simulator.set_deploy_policy(DeployPolicy::Open).unwrap_err();
+        assert!(
+            matches!(err, SimulatorError::DeployPolicyForbidden { actor, admin } if actor == BOB && admin == ALICE),
+            "{err}"
+        );
+        assert_eq!(simulator.deploy_policy(), policy);
+
+        simulator.set_actor(ALICE);
+        simulator.set_deploy_policy(DeployPolicy::Open).unwrap();
+    }
+
+    #[test]
+    fn hypersdk_contract_known_answer() {
+        // `codec.CreateAddress(0, sha256(sha256(code) || salt))` in the hypersdk
+        let code: Vec<u8> = (0..16).collect();
+        let expected: Address =
+            "00d3791ae1c6623590072873b1bb75b9e1ab806bb636461bb492390d35a4a89b92"
+                .parse()
+                .unwrap();
+        assert_eq!(hypersdk_contract_address(&code, b"salt"), expected);
+    }
+
+    #[test]
+    fn derived_addresses_are_deterministic() {
+        // `[0] || sha256(sha256(b"\0asm") || [1; 33] || 0u64.to_le_bytes())`
+        let expected: Address =
+            "00efb64ebf2c1b63459860eaa94c5e2dfff79d7b3e3150bb7b6e666daf60b58668"
+                .parse()
+                .unwrap();
+        assert_eq!(contract_address(&ALICE, 0, b"\0asm"), expected);
+
+        let (first, second) = (Simulator::new(), Simulator::new());
+        for simulator in [&first, &second] {
+            assert_eq!(
+                simulator.deploy(&ALICE, b"\0asm".to_vec()).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn redeploying_the_same_code_uses_a_new_address() {
+        let simulator = Simulator::new();
+        let code = vec![1, 2, 3];
+
+        let first = simulator.deploy(&ALICE, code.clone()).unwrap();
+        let second = simulator.deploy(&ALICE, code.clone()).unwrap();
+        let other = simulator.deploy(&BOB, code.clone()).unwrap();
+
+        assert_ne!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(second, contract_address(&ALICE, 1, &code));
+
+        let state = simulator.get_state();
+        let state = state.read().unwrap();
+        assert_eq!(state.get_deploy_nonce(&ALICE), 2);
+        assert_eq!(state.get_deploy_nonce(&BOB), 1);
+        for contract in [first, second, other] {
+            assert_eq!(state.get_contract(&contract), Some(&code));
+        }
+    }
+
+    #[test]
+    fn deploy_checks_the_deployer() {
+        let simulator = Simulator::new();
+        simulator
+            .set_deploy_policy(DeployPolicy::AdminOnly(ALICE))
+            .unwrap();
+
+        let err = simulator.deploy(&BOB, vec![1]).unwrap_err();
+        assert!(
+            matches!(err, SimulatorError::DeployForbidden { actor, .. } if actor == BOB),
+            "{err}"
+        );
+        assert_eq!(
+            simulator.get_state().read().unwrap().get_deploy_nonce(&BOB),
+            0
+        );
+        simulator.deploy(&ALICE, vec![1]).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Events emitted by contracts, see [`Simulator::get_events`].
+
+use crate::{Address, Simulator, SimulatorState};
+
+/// The most events a single contract call may emit, not counting the calls it makes.
+pub const MAX_EVENTS_PER_CALL: usize = 256;
+/// The most bytes of data a single event may carry.
+pub const MAX_EVENT_DATA_SIZE: usize = 64 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    /// The contract that emitted the event, or `None` for code run with [`Simulator::execute_wasm`].
+    pub contract: Option<Address>,
+    /// The account the call that emitted the event was made by.
+    pub actor: Address,
+    /// The height of the block the event was emitted in.
+    pub height: u64,
+    /// The timestamp of the block the event was emitted in.
+    pub timestamp: u64,
+    pub name: String,
+    pub data: Vec<u8>,
+    /// The event's position in the log since it was last cleared.
+    pub sequence: u64,
+}
+
+impl SimulatorState {
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    pub(crate) fn push_event(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+}
+
+impl Simulator {
+    /// Every event emitted since the simulator was created or last cleared, oldest first.
+    pub fn get_events(&self) -> Vec<Event> {
+        self.state.read().unwrap().events().to_vec()
+    }
+
+    pub fn clear_events(&self) {
+        self.state.write().unwrap().clear_events();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SimulatorError, ADDRESS_LEN};
+
+    // `emit` emits borsh `("Transfer", [1, 2])` then `("Approval", [3])`,
+    // `flood` emits empty events until it is stopped
+    const EMITTER: &str = r#"
+        (module
+            (import "contract" "emit_event" (func $emit_event (param i32 i32)))
+            (memory (export "memory") 1)
+            (data (i32.const 0) "\08\00\00\00Transfer\02\00\00\00\01\02")
+            (data (i32.const 32) "\08\00\00\00Approval\01\00\00\00\03")
+            (data (i32.const 64) "\05\00\00\00Flood\00\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "emit") (param i32)
+                (call $emit_event (i32.const 0) (i32.const 18))
+                (call $emit_event (i32.const 32) (i32.const 17)))
+            (func (export "flood") (param i32)
+                (loop $more
+                    (call $emit_event (i32.const 64) (i32.const 13))
+                    (br $more))))
+    "#;
+
+    const CONTRACT: Address = Address::new([1; ADDRESS_LEN]);
+    const ACTOR: Address = Address::new([2; ADDRESS_LEN]);
+
+    #[test]
+    fn events_are_collected_in_order() {
+        let simulator = Simulator::new();
+        simulator
+            .create_contract(CONTRACT, EMITTER.as_bytes().to_vec())
+            .unwrap();
+        simulator.set_actor(ACTOR);
+        simulator.set_height(7);
+        simulator.set_timestamp(1_000);
+
+        simulator
+            .call_contract(CONTRACT, "emit", &[], 100_000)
+            .unwrap();
+
+        assert_eq!(
+            simulator.get_events(),
+            [
+                Event {
+                    contract: Some(CONTRACT),
+                    actor: ACTOR,
+                    height: 7,
+                    timestamp: 1_000,
+                    name: "Transfer".to_string(),
+                    data: vec![1, 2],
+                    sequence: 0,
+                },
+                Event {
+                    contract: Some(CONTRACT),
+                    actor: ACTOR,
+                    height: 7,
+                    timestamp: 1_000,
+                    name: "Approval".to_string(),
+                    data: vec![3],
+                    sequence: 1,
+                },
+            ]
+        );
+
+        simulator.clear_events();
+        assert!(simulator.get_events().is_empty());
+    }
+
+    #[test]
+    fn too_many_events() {
+        let simulator = Simulator::new();
+
+        let err = simulator
+            .execute_wasm(EMITTER.as_bytes(), "flood", &[], u64::MAX)
+            .unwrap_err();
+
+        assert!(matches!(&err, SimulatorError::ContractExecution(msg)
+            if msg.contains(&format!("at most {MAX_EVENTS_PER_CALL} events"))), "{err}");
+        // the call failed, so none of the events it emitted are kept
+        assert!(simulator.get_events().is_empty());
+    }
+}
@@ -0,0 +1,386 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Dry runs that report what a call would change, see [`Simulator::explain`].
+
+use crate::{names, Address, Simulator, SimulatorError, SimulatorState};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    fmt::Write as _,
+    sync::atomic::Ordering,
+};
+
+/// Values longer than this many bytes are cut short when rendered.
+const MAX_RENDERED_LEN: usize = 32;
+
+/// What a call made through [`Simulator::explain`] would have done.
+#[derive(Debug)]
+pub struct Explanation {
+    pub contract: Address,
+    pub method: String,
+    pub result: Result<Vec<u8>, SimulatorError>,
+    pub gas_used: u64,
+    /// Every key written or deleted, sorted by key.
+    /// Keys are the raw storage keys, so those of contracts are prefixed with the contract's address.
+    pub state: Vec<StateChange>,
+    /// Every account whose balance changed, sorted by account.
+    pub balances: Vec<BalanceChange>,
+    /// The names of the contract and accounts above that were given one with [`Simulator::set_contract_name`].
+    pub names: BTreeMap<Address, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateChange {
+    pub key: Vec<u8>,
+    /// `None` if the key was not set.
+    pub before: Option<Vec<u8>>,
+    /// `None` if the key was deleted.
+    pub after: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub account: Address,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl BalanceChange {
+    pub fn delta(&self) -> i128 {
+        i128::from(self.after) - i128::from(self.before)
+    }
+}
+
+impl Simulator {
+    /// Calls `method` on `contract` like [`Simulator::call_contract`], then rolls back
+    /// everything the call did and reports what it changed.
+    /// [`Simulator::last_gas_used`] is left as it was before the dry run.
+    pub fn explain(&self, contract: Address, method: &str, params: &[u8], gas: u64) -> Explanation {
+        let snapshot = self.snapshot();
+        let last_gas_used = self.last_gas_used();
+
+        let result = self.call_contract(contract, method, params, gas);
+        let gas_used = self.last_gas_used();
+        let (state, balances) = diff(&snapshot.0, &self.state.read().unwrap());
+
+        self.restore(snapshot);
+        self.last_gas_used.store(last_gas_used, Ordering::Relaxed);
+        let names = self.names_of(
+            std::iter::once(&contract).chain(balances.iter().map(|change| &change.account)),
+        );
+
+        Explanation {
+            contract,
+            method: method.to_string(),
+            result,
+            gas_used,
+            state,
+            balances,
+            names,
+        }
+    }
+}
+
+fn diff(before: &SimulatorState, after: &SimulatorState) -> (Vec<StateChange>, Vec<BalanceChange>) {
+    let keys: BTreeSet<_> = before.state.keys().chain(after.state.keys()).collect();
+    let state = keys
+        .into_iter()
+        .filter_map(|key| {
+            let (old, new) = (before.state.get(key), after.state.get(key));
+            (old != new).then(|| StateChange {
+                key: key.clone(),
+                before: old.cloned(),
+                after: new.cloned(),
+            })
+        })
+        .collect();
+
+    let accounts: BTreeSet<_> = before
+        .balances
+        .keys()
+        .chain(after.balances.keys())
+        .collect();
+    let balances = accounts
+        .into_iter()
+        .filter_map(|account| {
+            let (old, new) = (before.get_balance(account), after.get_balance(account));
+            (old != new).then_some(BalanceChange {
+                account: *account,
+                before: old,
+                after: new,
+            })
+        })
+        .collect();
+
+    (state, balances)
+}
+
+impl Explanation {
+    /// Renders the explanation as a JSON object, with byte strings in hex.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        let _ = write!(
+            json,
+            r#"{{"contract":"{}","method":{},"#,
+            self.contract,
+            quote(&self.method)
+        );
+        match &self.result {
+            Ok(bytes) => {
+                let _ = write!(json, r#""result":{{"ok":"{}"}},"#, hex(bytes));
+            }
+            Err(err) => {
+                let _ = write!(json, r#""result":{{"err":{}}},"#, quote(&err.to_string()));
+            }
+        }
+        let _ = write!(json, r#""gas_used":{},"state":["#, self.gas_used);
+        for (i, change) in self.state.iter().enumerate() {
+            let value = |value: &Option<Vec<u8>>| {
+                value
+                    .as_deref()
+                    .map_or("null".to_string(), |value| format!(r#""{}""#, hex(value)))
+            };
+            let _ = write!(
+                json,
+                r#"{}{{"key":"{}","before":{},"after":{}}}"#,
+                if i == 0 { "" } else { "," },
+                hex(&change.key),
+                value(&change.before),
+                value(&change.after),
+            );
+        }
+        json.push_str(r#"],"balances":["#);
+        for (i, change) in self.balances.iter().enumerate() {
+            let _ = write!(
+                json,
+                r#"{}{{"account":"{}","before":{},"after":{}}}"#,
+                if i == 0 { "" } else { "," },
+                change.account,
+                change.before,
+                change.after,
+            );
+        }
+        json.push_str(r#"],"names":{"#);
+        for (i, (address, name)) in self.names.iter().enumerate() {
+            let _ = write!(
+                json,
+                r#"{}"{address}":{}"#,
+                if i == 0 { "" } else { "," },
+                quote(name)
+            );
+        }
+        json.push_str("}}");
+        json
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}.{}",
+            names::display(&self.names, &self.contract),
+            self.method
+        )?;
+        match &self.result {
+            Ok(bytes) => writeln!(f, "  result: ok {}", truncated(bytes))?,
+            Err(err) => writeln!(f, "  result: error: {err}")?,
+        }
+        writeln!(f, "  gas used: {}", self.gas_used)?;
+
+        if self.state.is_empty() {
+            writeln!(f, "  state: unchanged")?;
+        } else {
+            writeln!(f, "  state:")?;
+            for change in &self.state {
+                let value = |value: &Option<Vec<u8>>| {
+                    value.as_deref().map_or("(unset)".to_string(), truncated)
+                };
+                writeln!(
+                    f,
+                    "    {}: {} -> {}",
+                    truncated(&change.key),
+                    value(&change.before),
+                    value(&change.after)
+                )?;
+            }
+        }
+
+        if self.balances.is_empty() {
+            write!(f, "  balances: unchanged")
+        } else {
+            write!(f, "  balances:")?;
+            for change in &self.balances {
+                write!(
+                    f,
+                    "\n    {}: {} -> {} ({:+})",
+                    names::display(&self.names, &change.account),
+                    change.before,
+                    change.after,
+                    change.delta()
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn truncated(bytes: &[u8]) -> String {
+    if bytes.len() > MAX_RENDERED_LEN {
+        format!(
+            "0x{}... ({} bytes)",
+            hex(&bytes[..MAX_RENDERED_LEN]),
+            bytes.len()
+        )
+    } else {
+        format!("0x{}", hex(bytes))
+    }
+}
+
+fn quote(s: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", u32::from(c));
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ADDRESS_LEN;
+
+    // counts its calls in state under `count`
+    const COUNTER: &str = r#"
+        (module
+            (import "state" "get" (func $get (param i32 i32) (result i32)))
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `GetArgs { key: b"count" }`
+            (data (i32.const 0) "\05\00\00\00count")
+            ;; borsh `vec![(b"count", [n])]`, `n` is written at offset 49
+            (data (i32.const 32) "\01\00\00\00\05\00\00\00count\01\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "increment") (param i32)
+                (local $value i32)
+                (local.set $value (call $get (i32.const 0) (i32.const 9)))
+                (i32.store8 (i32.const 49)
+                    (if (result i32) (local.get $value)
+                        (then (i32.add (i32.load8_u (local.get $value)) (i32.const 1)))
+                        (else (i32.const 1))))
+                (call $put (i32.const 32) (i32.const 18))))
+    "#;
+
+    const CONTRACT: Address = Address::new([1; ADDRESS_LEN]);
+
+    fn counter() -> Simulator {
+        let simulator = Simulator::new();
+        simulator
+            .create_contract(CONTRACT, COUNTER.as_bytes().to_vec())
+            .unwrap();
+        simulator
+            .call_contract(CONTRACT, "increment", &[], 100_000)
+            .unwrap();
+        simulator
+    }
+
+    #[test]
+    fn explain_increment() {
+        let simulator = counter();
+        let count_key = SimulatorState::contract_key(&CONTRACT, b"count");
+
+        let explanation = simulator.explain(CONTRACT, "increment", &[], 100_000);
+
+        assert!(explanation.result.is_ok());
+        assert!(explanation.gas_used > 0);
+        assert_eq!(
+            explanation.state,
+            [StateChange {
+                key: count_key,
+                before: Some(vec![1]),
+                after: Some(vec![2]),
+            }]
+        );
+        assert!(explanation.balances.is_empty());
+
+        let rendered = explanation.to_string();
+        assert!(rendered.contains("0x01 -> 0x02"), "{rendered}");
+        assert!(rendered.contains("balances: unchanged"), "{rendered}");
+    }
+
+    #[test]
+    fn explain_commits_nothing() {
+        let simulator = counter();
+        let before = simulator.snapshot();
+        let gas_used = simulator.last_gas_used();
+
+        simulator.explain(CONTRACT, "increment", &[], 100_000);
+
+        assert_eq!(*simulator.get_state().read().unwrap(), *before.0);
+        assert_eq!(simulator.last_gas_used(), gas_used);
+    }
+
+    #[test]
+    fn explain_failed_call() {
+        let simulator = Simulator::new();
+
+        let explanation = simulator.explain(CONTRACT, "increment", &[], 100_000);
+
+        assert!(matches!(
+            explanation.result,
+            Err(SimulatorError::ContractNotFound(_))
+        ));
+        assert!(explanation.state.is_empty());
+    }
+
+    #[test]
+    fn to_json() {
+        let explanation = Explanation {
+            contract: CONTRACT,
+            method: "say \"hi\"".to_string(),
+            result: Ok(vec![0xab]),
+            gas_used: 7,
+            state: vec![StateChange {
+                key: vec![1],
+                before: None,
+                after: Some(vec![2]),
+            }],
+            balances: vec![BalanceChange {
+                account: CONTRACT,
+                before: 10,
+                after: 4,
+            }],
+            names: BTreeMap::from([(CONTRACT, "counter".to_string())]),
+        };
+
+        assert_eq!(
+            explanation.to_json(),
+            format!(
+                r#"{{"contract":"{CONTRACT}","method":"say \"hi\"","result":{{"ok":"ab"}},"gas_used":7,"state":[{{"key":"01","before":null,"after":"02"}}],"balances":[{{"account":"{CONTRACT}","before":10,"after":4}}],"names":{{"{CONTRACT}":"counter"}}}}"#
+            )
+        );
+        assert_eq!(explanation.balances[0].delta(), -6);
+    }
+
+    #[test]
+    fn long_values_are_truncated() {
+        let rendered = truncated(&[0xff; 40]);
+        assert_eq!(
+            rendered,
+            format!("0x{}... (40 bytes)", "ff".repeat(MAX_RENDERED_LEN))
+        );
+    }
+}
@@ -0,0 +1,355 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Aggregates over every key with a prefix for the `state.fold_prefix` import, so contracts can
+//! summarize a prefix in one host call instead of paging through it with `state.scan`.
+//!
+//! The operations and errors are those of wasmlanche's `FoldOp` and `FoldError`.
+
+use borsh::BorshSerialize;
+
+/// Fuel charged for every entry an operation visits.
+pub const FOLD_GAS_PER_ENTRY: u64 = 5;
+
+/// Counts the entries, returning a `u64`.
+const COUNT: u32 = 0;
+/// Adds up the entries' `u64` values, returning `Result<u64, FoldError>`.
+const SUM_U64: u32 = 1;
+/// Finds the entry with the largest `u64` value, returning `Result<Option<(key, u64)>, FoldError>`.
+const MAX_U64: u32 = 2;
+/// Collects the first `limit: u32` keys in ascending order, returning a `Vec<key>`.
+const KEYS: u32 = 3;
+
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(test, derive(borsh::BorshDeserialize))]
+enum FoldError {
+    /// The value under `key` is not a borsh-encoded `u64`.
+    InvalidValue {
+        key: Vec<u8>,
+    },
+    Overflow,
+}
+
+// the simulator does not depend on borsh's derive macros outside of its tests
+impl BorshSerialize for FoldError {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        match self {
+            Self::InvalidValue { key } => (0u8, key).serialize(writer),
+            Self::Overflow => 1u8.serialize(writer),
+        }
+    }
+}
+
+/// The borsh-encoded result of an operation, and how many entries it visited.
+pub(crate) struct Folded {
+    pub result: Vec<u8>,
+    pub visited: u64,
+}
+
+/// Runs the operation `op` with the borsh-encoded argument `arg` over `entries`, which must be in ascending key order.
+///
+/// Only `affordable` entries are paid for, so the walk stops at the one after them and reports it
+/// as visited, leaving the caller's charge for [`Folded::visited`] to run out of fuel.
+pub(crate) fn fold<'a>(
+    op: u32,
+    arg: &[u8],
+    entries: impl Iterator<Item = (&'a [u8], &'a [u8])>,
+    affordable: u64,
+) -> wasmtime::Result<Folded> {
+    let mut visited = 0;
+    let result = {
+        let mut entries = entries
+            .take(usize::try_from(affordable.saturating_add(1)).unwrap_or(usize::MAX))
+            .inspect(|_| visited += 1);
+        match op {
+            COUNT => borsh::to_vec(&(entries.count() as u64))?,
+            SUM_U64 => borsh::to_vec(&entries.try_fold(0u64, |sum, (key, value)| {
+                sum.checked_add(decode(key, value)?)
+                    .ok_or(FoldError::Overflow)
+            }))?,
+            MAX_U64 => borsh::to_vec(&entries.try_fold(None, |max, (key, value)| {
+                let value = decode(key, value)?;
+                Ok::<_, FoldError>(match max {
+                    Some((key, max)) if max >= value => Some((key, max)),
+                    _ => Some((key.to_vec(), value)),
+                })
+            }))?,
+            KEYS => {
+                let limit: u32 = borsh::from_slice(arg)?;
+                let keys: Vec<_> = entries.take(limit as usize).map(|(key, _)| key).collect();
+                borsh::to_vec(&keys)?
+            }
+            _ => return Err(wasmtime::Error::msg(format!("unknown fold operation {op}"))),
+        }
+    };
+
+    Ok(Folded { result, visited })
+}
+
+fn decode(key: &[u8], value: &[u8]) -> Result<u64, FoldError> {
+    value
+        .try_into()
+        .map(u64::from_le_bytes)
+        .map_err(|_| FoldError::InvalidValue { key: key.to_vec() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, Simulator, SimulatorError, SimulatorState, ADDRESS_LEN};
+
+    // `fold` takes `op: u32, prefix_len: u32, prefix, arg` laid out back to back and passes them on to
+    // `state.fold_prefix`, `scan` passes its params on to `state.scan`, and both return the result
+    const FOLD: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "state" "scan" (func $scan (param i32 i32) (result i32)))
+            (import "state" "fold_prefix" (func $fold (param i32 i32 i32 i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $last_len (mut i32) (i32.const 0))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.set $last_len (local.get $len))
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "fold") (param $ptr i32)
+                (local $prefix_len i32)
+                (local $result i32)
+                (local.set $prefix_len (i32.load (i32.add (local.get $ptr) (i32.const 4))))
+                (local.set $result
+                    (call $fold
+                        (i32.add (local.get $ptr) (i32.const 8))
+                        (local.get $prefix_len)
+                        (i32.load (local.get $ptr))
+                        (i32.add (local.get $ptr) (i32.add (local.get $prefix_len) (i32.const 8)))
+                        (i32.sub (global.get $last_len) (i32.add (local.get $prefix_len) (i32.const 8)))))
+                (call $set_call_result (local.get $result) (global.get $last_len)))
+            (func (export "scan") (param $ptr i32)
+                (local $page i32)
+                (local.set $page (call $scan (local.get $ptr) (global.get $last_len)))
+                (call $set_call_result (local.get $page) (global.get $last_len))))
+    "#;
+
+    const CONTRACT: Address = Address::new([1; ADDRESS_LEN]);
+    const PREFIX: &[u8] = b"balance/";
+
+    type Max = Result<Option<(Vec<u8>, u64)>, FoldError>;
+    type Entries = Vec<(Vec<u8>, Vec<u8>)>;
+
+    /// A contract with 500 `u64` balances under [`PREFIX`], and some other keys around them.
+    fn populated() -> Simulator {
+        let simulator = Simulator::new();
+        simulator
+            .create_contract(CONTRACT, FOLD.as_bytes().to_vec())
+            .unwrap();
+
+        let state = simulator.get_state();
+        let mut state = state.write().unwrap();
+        for i in 0..500u64 {
+            let key = [PREFIX, &i.to_be_bytes()].concat();
+            let value = (i * 7919) % 1000;
+            state.set_value(
+                SimulatorState::contract_key(&CONTRACT, &key),
+                value.to_le_bytes().to_vec(),
+            );
+        }
+        state.set_value(SimulatorState::contract_key(&CONTRACT, b"balancd"), vec![1]);
+        state.set_value(SimulatorState::contract_key(&CONTRACT, b"balancf"), vec![1]);
+        state.set_value(
+            SimulatorState::contract_key(&Address::new([2; ADDRESS_LEN]), PREFIX),
+            vec![1],
+        );
+        drop(state);
+
+        simulator
+    }
+
+    fn call_fold(
+        simulator: &Simulator,
+        op: u32,
+        prefix: &[u8],
+        arg: &[u8],
+    ) -> Result<Vec<u8>, SimulatorError> {
+        let params = [
+            &op.to_le_bytes(),
+            &(prefix.len() as u32).to_le_bytes(),
+            prefix,
+            arg,
+        ]
+        .concat();
+        simulator.call_contract(CONTRACT, "fold", &params, 1_000_000)
+    }
+
+    fn fold_as<T: borsh::BorshDeserialize>(simulator: &Simulator, op: u32, arg: &[u8]) -> T {
+        borsh::from_slice(&call_fold(simulator, op, PREFIX, arg).unwrap()).unwrap()
+    }
+
+    /// Every entry under [`PREFIX`], read a page at a time through `state.scan` the way a contract would,
+    /// along with how many host calls that took.
+    fn scan_pages(simulator: &Simulator) -> (Entries, u64) {
+        const PAGE: u32 = 16;
+
+        let mut entries = Vec::new();
+        let mut calls = 0;
+        loop {
+            let args = borsh::to_vec(&(PREFIX, entries.len() as u32, PAGE)).unwrap();
+            let page = simulator
+                .call_contract(CONTRACT, "scan", &args, 1_000_000)
+                .unwrap();
+            let (count, page): (u32, Entries) = borsh::from_slice(&page).unwrap();
+            calls += 1;
+            entries.extend(page);
+            if entries.len() == count as usize {
+                return (entries, calls);
+            }
+        }
+    }
+
+    #[test]
+    fn folds_match_paged_scans() {
+        let simulator = populated();
+        let (entries, calls) = scan_pages(&simulator);
+        let values: Vec<_> = entries
+            .iter()
+            .map(|(key, value)| {
+                (
+                    key.clone(),
+                    u64::from_le_bytes(value[..].try_into().unwrap()),
+                )
+            })
+            .collect();
+        assert_eq!(values.len(), 500);
+        assert!(calls > 30, "{calls}");
+
+        assert_eq!(fold_as::<u64>(&simulator, COUNT, &[]), 500);
+
+        let sum = values.iter().map(|(_, value)| value).sum();
+        assert_eq!(
+            fold_as::<Result<u64, FoldError>>(&simulator, SUM_U64, &[]),
+            Ok(sum)
+        );
+
+        let max = values.iter().max_by_key(|(_, value)| value).unwrap();
+        let first_max = values.iter().find(|(_, value)| value == &max.1).cloned();
+        assert_eq!(fold_as::<Max>(&simulator, MAX_U64, &[]), Ok(first_max));
+
+        let keys: Vec<_> = values.iter().take(10).map(|(key, _)| key.clone()).collect();
+        assert_eq!(
+            fold_as::<Vec<Vec<u8>>>(&simulator, KEYS, &borsh::to_vec(&10u32).unwrap()),
+            keys
+        );
+        assert_eq!(
+            fold_as::<Vec<Vec<u8>>>(&simulator, KEYS, &borsh::to_vec(&1000u32).unwrap()).len(),
+            500
+        );
+    }
+
+    #[test]
+    fn empty_prefixes() {
+        let simulator = populated();
+        let fold = |op, arg: &[u8]| call_fold(&simulator, op, b"nothing/", arg).unwrap();
+
+        assert_eq!(fold(COUNT, &[]), borsh::to_vec(&0u64).unwrap());
+        assert_eq!(
+            fold(SUM_U64, &[]),
+            borsh::to_vec(&Ok::<u64, FoldError>(0)).unwrap()
+        );
+        assert_eq!(fold(MAX_U64, &[]), borsh::to_vec(&Max::Ok(None)).unwrap());
+        assert_eq!(
+            fold(KEYS, &[5, 0, 0, 0]),
+            borsh::to_vec(&Vec::<Vec<u8>>::new()).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_values_name_their_key() {
+        let simulator = populated();
+        let key = [PREFIX, &250u64.to_be_bytes()].concat();
+        simulator
+            .get_state()
+            .write()
+            .unwrap()
+            .set_value(SimulatorState::contract_key(&CONTRACT, &key), vec![1, 2, 3]);
+
+        let invalid = Err(FoldError::InvalidValue { key });
+        assert_eq!(
+            fold_as::<Result<u64, FoldError>>(&simulator, SUM_U64, &[]),
+            invalid
+        );
+        assert_eq!(
+            fold_as::<Max>(&simulator, MAX_U64, &[]),
+            invalid.map(|_: u64| None)
+        );
+        assert_eq!(fold_as::<u64>(&simulator, COUNT, &[]), 500);
+    }
+
+    #[test]
+    fn sums_overflow() {
+        let simulator = populated();
+        let key = [PREFIX, &0u64.to_be_bytes()].concat();
+        simulator.get_state().write().unwrap().set_value(
+            SimulatorState::contract_key(&CONTRACT, &key),
+            u64::MAX.to_le_bytes().to_vec(),
+        );
+
+        assert_eq!(
+            fold_as::<Result<u64, FoldError>>(&simulator, SUM_U64, &[]),
+            Err(FoldError::Overflow)
+        );
+    }
+
+    #[test]
+    fn gas_is_charged_per_visited_entry() {
+        let simulator = populated();
+        let gas_used = |op, arg: &[u8]| {
+            call_fold(&simulator, op, PREFIX, arg).unwrap();
+            simulator.last_gas_used()
+        };
+
+        let few = gas_used(KEYS, &borsh::to_vec(&1u32).unwrap());
+        let all = gas_used(KEYS, &borsh::to_vec(&500u32).unwrap());
+        assert!(all - few >= 499 * FOLD_GAS_PER_ENTRY, "{few} {all}");
+
+        let params = [
+            &COUNT.to_le_bytes()[..],
+            &(PREFIX.len() as u32).to_le_bytes(),
+            PREFIX,
+        ]
+        .concat();
+        let err = simulator
+            .call_contract(CONTRACT, "fold", &params, 500 * FOLD_GAS_PER_ENTRY)
+            .unwrap_err();
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+    }
+
+    #[test]
+    fn folds_stop_at_the_first_unaffordable_entry() {
+        let entries = (0u64..).map(|_| -> (&[u8], &[u8]) { (b"key", b"value") });
+        let folded = fold(COUNT, &[], entries, 10).unwrap();
+        assert_eq!(folded.visited, 11);
+
+        let simulator = populated();
+        let params = [
+            &COUNT.to_le_bytes()[..],
+            &(PREFIX.len() as u32).to_le_bytes(),
+            PREFIX,
+        ]
+        .concat();
+        let gas = 100 * FOLD_GAS_PER_ENTRY;
+        let err = simulator
+            .call_contract(CONTRACT, "fold", &params, gas)
+            .unwrap_err();
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+        assert_eq!(simulator.last_gas_used(), gas);
+    }
+
+    #[test]
+    fn unknown_operations_trap() {
+        let simulator = populated();
+        let err = call_fold(&simulator, 9, PREFIX, &[]).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown fold operation 9"),
+            "{err}"
+        );
+    }
+}
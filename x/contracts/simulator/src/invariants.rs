@@ -0,0 +1,388 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Assertions checked against the state after every call, see [`Simulator::register_invariant`].
+
+use crate::{Simulator, SimulatorError, SimulatorState};
+
+type Check = Box<dyn Fn(&SimulatorState) -> Result<(), String> + Send + Sync>;
+
+/// What happens when an invariant does not hold.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvariantMode {
+    /// The call that broke the invariant fails with [`SimulatorError::InvariantViolated`].
+    #[default]
+    Fail,
+    /// The violation is added to [`Simulator::invariant_violations`] and the call's result is left alone.
+    Record,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvariantViolation {
+    pub name: String,
+    pub message: String,
+    /// The index of the call after which the invariant was checked, counting every call made
+    /// through the simulator from zero, or `None` if it was checked after [`Simulator::advance_block`].
+    pub call_index: Option<u64>,
+}
+
+impl From<InvariantViolation> for SimulatorError {
+    fn from(violation: InvariantViolation) -> Self {
+        let InvariantViolation {
+            name,
+            message,
+            call_index,
+        } = violation;
+        SimulatorError::InvariantViolated {
+            name,
+            message,
+            call_index,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Invariants {
+    checks: Vec<(String, Check)>,
+    mode: InvariantMode,
+    violations: Vec<InvariantViolation>,
+}
+
+impl Simulator {
+    /// Checks `check` against the state after every call and every [`Simulator::advance_block`],
+    /// in the order the invariants were registered.
+    ///
+    /// Checks run outside the contract, so they use no gas, and the state is left as the call left it
+    /// whether or not they pass.
+    pub fn register_invariant(
+        &self,
+        name: impl Into<String>,
+        check: impl Fn(&SimulatorState) -> Result<(), String> + Send + Sync + 'static,
+    ) {
+        self.invariants
+            .write()
+            .unwrap()
+            .checks
+            .push((name.into(), Box::new(check)));
+    }
+
+    pub fn invariant_mode(&self) -> InvariantMode {
+        self.invariants.read().unwrap().mode
+    }
+
+    /// Sets what happens when an invariant does not hold, [`InvariantMode::Fail`] by default.
+    pub fn set_invariant_mode(&self, mode: InvariantMode) {
+        self.invariants.write().unwrap().mode = mode;
+    }
+
+    /// Every violation recorded under [`InvariantMode::Record`], oldest first.
+    pub fn invariant_violations(&self) -> Vec<InvariantViolation> {
+        self.invariants.read().unwrap().violations.clone()
+    }
+
+    /// Runs every invariant, returning the first violation under [`InvariantMode::Fail`].
+    pub(crate) fn check_invariants(&self, call_index: Option<u64>) -> Result<(), SimulatorError> {
+        let mut invariants = self.invariants.write().unwrap();
+        let state = self.state.read().unwrap();
+
+        let mut violations = invariants.checks.iter().filter_map(|(name, check)| {
+            check(&state).err().map(|message| InvariantViolation {
+                name: name.clone(),
+                message,
+                call_index,
+            })
+        });
+
+        match invariants.mode {
+            InvariantMode::Fail => violations
+                .next()
+                .map_or(Ok(()), |violation| Err(violation.into())),
+            InvariantMode::Record => {
+                let violations: Vec<_> = violations.collect();
+                invariants.violations.extend(violations);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Describes where an invariant was found broken, for [`SimulatorError::InvariantViolated`].
+pub(crate) fn describe_call(call_index: &Option<u64>) -> String {
+    match call_index {
+        Some(index) => format!(" after call {index}"),
+        None => " after advancing the block".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, ADDRESS_LEN};
+
+    // a token whose balances are 8 byte values under 1 byte account keys, and whose supply is under the key 255
+    // `mint` takes `(to: u8, amount: u64)` and `transfer` takes `(from: u8, to: u8, amount: u64)`,
+    // and transfers of more than the sender's balance do nothing
+    const TOKEN: &str = r#"
+        (module
+            (import "state" "get" (func $get (param i32 i32) (result i32)))
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `GetArgs { key }`, the key is written at offset 4
+            (data (i32.const 0) "\01\00\00\00")
+            ;; borsh `vec![([key], value)]`, the key is written at offset 24 and the value at 29
+            (data (i32.const 16) "\01\00\00\00\01\00\00\00\00\08\00\00\00")
+            ;; borsh `vec![([key], value), ([key], value)]`, with keys at 72 and 89 and values at 77 and 94
+            (data (i32.const 64) "\02\00\00\00\01\00\00\00\00\08\00\00\00")
+            (data (i32.const 85) "\01\00\00\00\00\08\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func $balance (param $account i32) (result i64)
+                (local $ptr i32)
+                (i32.store8 (i32.const 4) (local.get $account))
+                (local.set $ptr (call $get (i32.const 0) (i32.const 5)))
+                (if (result i64) (local.get $ptr)
+                    (then (i64.load (local.get $ptr)))
+                    (else (i64.const 0))))
+            (func $set (param $account i32) (param $balance i64)
+                (i32.store8 (i32.const 24) (local.get $account))
+                (i64.store (i32.const 29) (local.get $balance))
+                (call $put (i32.const 16) (i32.const 21)))
+            (func (export "mint") (param $ptr i32)
+                (local $to i32)
+                (local $amount i64)
+                (local.set $to (i32.load8_u (local.get $ptr)))
+                (local.set $amount (i64.load (i32.add (local.get $ptr) (i32.const 1))))
+                (call $set (local.get $to) (i64.add (call $balance (local.get $to)) (local.get $amount)))
+                (call $set (i32.const 255) (i64.add (call $balance (i32.const 255)) (local.get $amount))))
+            ;; debits the sender before reading the recipient's balance
+            (func $move (param $from i32) (param $to i32) (param $amount i64) (param $balance i64)
+                (call $set (local.get $from) (i64.sub (local.get $balance) (local.get $amount)))
+                (call $set (local.get $to) (i64.add (call $balance (local.get $to)) (local.get $amount))))
+            ;; reads both balances up front, so a transfer to the sender credits them without the debit
+            (func $move_stale (param $from i32) (param $to i32) (param $amount i64) (param $balance i64)
+                (i32.store8 (i32.const 72) (local.get $from))
+                (i64.store (i32.const 77) (i64.sub (local.get $balance) (local.get $amount)))
+                (i32.store8 (i32.const 89) (local.get $to))
+                (i64.store (i32.const 94) (i64.add (call $balance (local.get $to)) (local.get $amount)))
+                (call $put (i32.const 64) (i32.const 38)))
+            (func (export "transfer") (param $ptr i32)
+                (local $from i32)
+                (local $to i32)
+                (local $amount i64)
+                (local $balance i64)
+                ;; `state.get` results are written over the params, so they are read first
+                (local.set $from (i32.load8_u (local.get $ptr)))
+                (local.set $to (i32.load8_u (i32.add (local.get $ptr) (i32.const 1))))
+                (local.set $amount (i64.load (i32.add (local.get $ptr) (i32.const 2))))
+                (local.set $balance (call $balance (local.get $from)))
+                (if (i64.ge_u (local.get $balance) (local.get $amount))
+                    (then
+                        (call $move (local.get $from) (local.get $to) (local.get $amount) (local.get $balance))))))
+    "#;
+
+    const TOKEN_ADDRESS: Address = Address::new([1; ADDRESS_LEN]);
+    const SUPPLY_KEY: u8 = 255;
+    const ACCOUNTS: u8 = 4;
+
+    fn token(code: &str) -> Simulator {
+        let simulator = Simulator::new();
+        simulator
+            .create_contract(TOKEN_ADDRESS, code.as_bytes().to_vec())
+            .unwrap();
+        simulator.register_invariant("supply", |state| {
+            let (mut supply, mut sum) = (0, 0);
+            for (key, value) in state.get_by_prefix(TOKEN_ADDRESS.as_bytes()) {
+                let value = u64::from_le_bytes(value.try_into().unwrap());
+                match key[ADDRESS_LEN..] {
+                    [SUPPLY_KEY] => supply = value,
+                    _ => sum += value,
+                }
+            }
+            if supply == sum {
+                Ok(())
+            } else {
+                Err(format!(
+                    "the supply is {supply}, but the balances add up to {sum}"
+                ))
+            }
+        });
+        simulator
+    }
+
+    enum Op {
+        Mint { to: u8, amount: u64 },
+        Transfer { from: u8, to: u8, amount: u64 },
+    }
+
+    /// A fixed pseudo-random sequence of mints and transfers between a few accounts.
+    fn ops() -> Vec<Op> {
+        // xorshift, so the sequence is the same on every run
+        let mut seed = 0x9e37_79b9_7f4a_7c15_u64;
+        let mut next = move |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        (0..200)
+            .map(|_| {
+                let account = |next: &mut dyn FnMut(u64) -> u64| next(u64::from(ACCOUNTS)) as u8;
+                if next(4) == 0 {
+                    Op::Mint {
+                        to: account(&mut next),
+                        amount: next(100),
+                    }
+                } else {
+                    Op::Transfer {
+                        from: account(&mut next),
+                        to: account(&mut next),
+                        amount: next(150),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn apply(simulator: &Simulator, op: &Op) -> Result<Vec<u8>, SimulatorError> {
+        let (method, params) = match *op {
+            Op::Mint { to, amount } => ("mint", borsh::to_vec(&(to, amount)).unwrap()),
+            Op::Transfer { from, to, amount } => {
+                ("transfer", borsh::to_vec(&(from, to, amount)).unwrap())
+            }
+        };
+        simulator.call_contract(TOKEN_ADDRESS, method, &params, 1_000_000)
+    }
+
+    #[test]
+    fn supply_holds_for_correct_token() {
+        let simulator = token(TOKEN);
+
+        for op in ops() {
+            apply(&simulator, &op).unwrap();
+        }
+
+        let state = simulator.get_state();
+        let supply = state
+            .read()
+            .unwrap()
+            .get_contract_value(&TOKEN_ADDRESS, &[SUPPLY_KEY])
+            .map(|value| u64::from_le_bytes(value[..].try_into().unwrap()));
+        assert!(supply > Some(0));
+    }
+
+    #[test]
+    fn minting_bug_is_caught_where_it_happens() {
+        let simulator = token(&TOKEN.replace("(call $move ", "(call $move_stale "));
+
+        // the bug mints the amount of the first transfer to the sender that the sender can afford
+        let mut balances = [0; ACCOUNTS as usize];
+        let buggy = ops().iter().position(|op| match *op {
+            Op::Mint { to, amount } => {
+                balances[to as usize] += amount;
+                false
+            }
+            Op::Transfer { from, to, amount } => {
+                let affordable = balances[from as usize] >= amount;
+                if affordable {
+                    balances[from as usize] -= amount;
+                    balances[to as usize] += amount;
+                }
+                from == to && affordable && amount > 0
+            }
+        });
+        let buggy = buggy.expect("the sequence should transfer to the sender");
+
+        let ops = ops();
+        for op in &ops[..buggy] {
+            apply(&simulator, op).unwrap();
+        }
+        let before = simulator.snapshot();
+
+        let err = apply(&simulator, &ops[buggy]).unwrap_err();
+
+        let SimulatorError::InvariantViolated {
+            name,
+            message,
+            call_index,
+        } = &err
+        else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(name, "supply");
+        assert!(message.starts_with("the supply is"), "{message}");
+        assert_eq!(*call_index, Some(buggy as u64));
+        assert!(
+            err.to_string().contains(&format!("after call {buggy}")),
+            "{err}"
+        );
+
+        // the broken state is kept for inspection
+        assert_ne!(*simulator.get_state().read().unwrap(), *before.0);
+    }
+
+    #[test]
+    fn record_mode_keeps_results() {
+        let simulator = token(TOKEN);
+        simulator.set_invariant_mode(InvariantMode::Record);
+        simulator.register_invariant("never", |_| Err("broken".to_string()));
+
+        apply(&simulator, &Op::Mint { to: 0, amount: 5 }).unwrap();
+        apply(&simulator, &Op::Mint { to: 1, amount: 5 }).unwrap();
+
+        let violations = simulator.invariant_violations();
+        assert_eq!(
+            violations,
+            [0, 1].map(|call_index| InvariantViolation {
+                name: "never".to_string(),
+                message: "broken".to_string(),
+                call_index: Some(call_index),
+            })
+        );
+    }
+
+    #[test]
+    fn checks_use_no_gas() {
+        let op = Op::Mint { to: 0, amount: 5 };
+        let unchecked = token(TOKEN);
+        apply(&unchecked, &op).unwrap();
+
+        let checked = token(TOKEN);
+        checked.register_invariant("slow", |state| {
+            for _ in 0..1000 {
+                state.get_by_prefix(&[]).count();
+            }
+            Ok(())
+        });
+        apply(&checked, &op).unwrap();
+
+        assert_eq!(checked.last_gas_used(), unchecked.last_gas_used());
+    }
+
+    #[test]
+    fn checked_after_blocks() {
+        let simulator = Simulator::new();
+        simulator.register_invariant("height", |state| {
+            if state.get_height() < 2 {
+                Ok(())
+            } else {
+                Err("too tall".to_string())
+            }
+        });
+
+        simulator.advance_block(1).unwrap();
+        let err = simulator.advance_block(1).unwrap_err();
+
+        assert!(matches!(
+            err,
+            SimulatorError::InvariantViolated {
+                call_index: None,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Invariant 'height' violated after advancing the block: too tall"
+        );
+        assert_eq!(simulator.get_height(), 2);
+    }
+}
@@ -3,13 +3,70 @@
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 use std::collections::HashMap;
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
-use std::sync::{Arc, RwLock};
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, OnceLock, RwLock,
+};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::time::{Duration, Instant};
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 use std::str::FromStr;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::{
+    collections::BTreeMap,
+    ops::Bound,
+    path::{Path, PathBuf},
+};
 
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 use thiserror::Error;
 
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use addr::BECH32_GAS_PER_BYTE;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use bench::{BenchOptions, BenchReport, Stats};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use deploy::{contract_address, hypersdk_contract_address, DeployPolicy, HYPERSDK_TYPE_ID};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use events::{Event, MAX_EVENTS_PER_CALL, MAX_EVENT_DATA_SIZE};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use explain::{BalanceChange, Explanation, StateChange};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use fold::FOLD_GAS_PER_ENTRY;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use invariants::{InvariantMode, InvariantViolation};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use runtime::{DEFAULT_MEMORY_LIMIT, MAX_CALL_DEPTH, SET_RESULT_COST, STATE_WRITE_COST};
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod addr;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod backtrace;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod bench;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod deploy;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod events;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod explain;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod fold;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod invariants;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod names;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod runtime;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod timeout;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+mod trace;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod suggest;
+
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 #[derive(Debug, Error)]
 pub enum SimulatorError {
@@ -21,76 +78,520 @@ pub enum SimulatorError {
     Utf8(#[from] std::str::Utf8Error),
     #[error("Parse error: {0}")]
     Parse(#[from] std::num::ParseIntError),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("out of gas")]
+    OutOfGas,
+    #[error("memory limit exceeded")]
+    MemoryLimitExceeded,
+    #[error("execution timed out")]
+    Timeout,
+    #[error("No contract deployed at {0}")]
+    ContractNotFound(Address),
+    #[error("Corrupt state file: {0}")]
+    Corrupt(String),
+    #[error("{actor} may not deploy contracts under the {policy} deploy policy")]
+    DeployForbidden { actor: Address, policy: DeployPolicy },
+    #[error("{actor} may not change the deploy policy, only its admin {admin} may")]
+    DeployPolicyForbidden { actor: Address, admin: Address },
+    #[error("Decode error: {0}")]
+    Decode(std::io::Error),
+    #[error("Invalid wasm in {}: {reason}", .path.display())]
+    InvalidWasm { path: PathBuf, reason: String },
+    #[error("Invariant '{name}' violated{}: {message}", invariants::describe_call(.call_index))]
+    InvariantViolated {
+        name: String,
+        message: String,
+        /// See [`InvariantViolation::call_index`].
+        call_index: Option<u64>,
+    },
+    #[error("Advancing the block overflows its height or timestamp")]
+    BlockOverflow,
+    #[error("The name '{name}' is already used by {address}")]
+    NameTaken { name: String, address: Address },
+    #[error("Function '{method}' not found in contract{}", suggest::format_hint(.did_you_mean, .available))]
+    MethodNotFound {
+        method: String,
+        /// The closest export, if it is a likely typo of `method`.
+        did_you_mean: Option<String>,
+        /// The exports closest to `method`, nearest first.
+        available: Vec<String>,
+    },
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl SimulatorError {
+    /// Builds a [`SimulatorError::MethodNotFound`] ranking `exports` against `method`.
+    pub fn method_not_found<'a>(method: &str, exports: impl IntoIterator<Item = &'a str>) -> Self {
+        let (did_you_mean, available) = suggest::suggest(method, exports);
+        Self::MethodNotFound {
+            method: method.to_string(),
+            did_you_mean,
+            available,
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
-pub struct Address(pub Vec<u8>);
+/// Byte length of an [`Address`], matching `wasmlanche::Address::LEN`.
+pub const ADDRESS_LEN: usize = 33;
+
+/// A 33 byte account address with the same layout as `wasmlanche::Address`.
+///
+/// This used to be `Address(pub Vec<u8>)`. Code written against that shape does not compile
+/// any more: build addresses with [`Address::new`] or [`Address::try_from`] instead of
+/// `Address::new(vec)`, and read them with [`Address::as_bytes`] instead of the `.0` field.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address([u8; ADDRESS_LEN]);
 
 impl Address {
+    pub const LEN: usize = ADDRESS_LEN;
+
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
     }
 
-    pub fn new(bytes: Vec<u8>) -> Self {
+    pub const fn new(bytes: [u8; ADDRESS_LEN]) -> Self {
+        Address(bytes)
+    }
+
+    pub fn into_bytes(self) -> [u8; ADDRESS_LEN] {
+        self.0
+    }
+
+    /// Converts `bytes`, for callers migrating from the `Vec<u8>` based address.
+    ///
+    /// # Panics
+    /// Panics unless `bytes` is [`ADDRESS_LEN`] bytes long, use [`Address::try_from`] to handle that.
+    #[deprecated(note = "use `Address::new` with an array or `Address::try_from`")]
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn from_vec(bytes: Vec<u8>) -> Self {
+        Self::try_from(bytes).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Copies the address into a `Vec<u8>`, for callers migrating from the `Vec<u8>` based address.
+    #[deprecated(note = "use `Address::as_bytes` or `Address::into_bytes`")]
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Lowercase hex of the address, without a `0x` prefix. Parses back with [`FromStr`].
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn to_hex(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl core::fmt::Display for Address {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+// hex rather than 33 decimal bytes, so addresses read the same in errors and assertions
+impl core::fmt::Debug for Address {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Address({self})")
+    }
+}
+
+impl From<[u8; ADDRESS_LEN]> for Address {
+    fn from(bytes: [u8; ADDRESS_LEN]) -> Self {
         Address(bytes)
     }
 }
 
+impl From<Address> for [u8; ADDRESS_LEN] {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl TryFrom<&[u8]> for Address {
+    type Error = SimulatorError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; ADDRESS_LEN]>::try_from(bytes)
+            .map(Address)
+            .map_err(|_| {
+                SimulatorError::InvalidAddress(format!(
+                    "expected {ADDRESS_LEN} bytes, got {}",
+                    bytes.len()
+                ))
+            })
+    }
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl TryFrom<Vec<u8>> for Address {
+    type Error = SimulatorError;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(bytes.as_slice())
+    }
+}
+
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 impl FromStr for Address {
     type Err = SimulatorError;
 
+    /// Parses the hex encoding of an address, with or without a `0x` prefix.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Address(s.as_bytes().to_vec()))
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+
+        if !hex.len().is_multiple_of(2) || !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(SimulatorError::InvalidAddress(format!("'{s}' is not valid hex")));
+        }
+
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("checked to be hex digits"))
+            .collect();
+
+        Self::try_from(bytes)
     }
 }
 
+/// The largest contract the simulator loads from a file, matching the runtime's default `MaxContractSize`.
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
-#[derive(Default)]
+pub const MAX_CONTRACT_SIZE: usize = 1024 * 1024;
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Version written at the start of files saved by [`SimulatorState::save_to_file`].
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+const STATE_FORMAT_VERSION: u8 = 3;
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SimulatorState {
-    values: HashMap<Vec<u8>, Vec<u8>>,
+    /// Contract storage, read and written through the `state` imports.
+    state: BTreeMap<Vec<u8>, Vec<u8>>,
+    balances: HashMap<Address, u64>,
+    /// Code of each deployed contract.
+    contracts: HashMap<Address, Vec<u8>>,
+    /// How many contracts each deployer has deployed with [`Simulator::deploy`].
+    deploy_nonces: HashMap<Address, u64>,
+    /// Height of the current block, read through the `env.height` import.
+    height: u64,
+    /// Timestamp of the current block, read through the `env.timestamp` import.
+    timestamp: u64,
+    /// Events emitted through the `contract.emit_event` import, oldest first. These are not saved to files.
+    events: Vec<Event>,
 }
 
+/// A copy of a [`SimulatorState`] taken by [`SimulatorState::snapshot`].
+/// Cloning a snapshot is cheap.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[derive(Clone)]
+pub struct StateSnapshot(Arc<SimulatorState>);
+
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 impl SimulatorState {
     pub fn new() -> Self {
-        Self {
-            values: HashMap::new(),
-        }
+        Self::default()
     }
 
     pub fn get_value(&self, key: &[u8]) -> Option<&Vec<u8>> {
-        self.values.get(key)
+        self.state.get(key)
     }
 
     pub fn set_value(&mut self, key: Vec<u8>, value: Vec<u8>) {
-        self.values.insert(key, value);
+        self.state.insert(key, value);
+    }
+
+    /// Removes the value at `key`, returning it if there was one.
+    pub fn delete_value(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.state.remove(key)
+    }
+
+    /// Iterates over the keys from `start` up to but excluding `end` in ascending order.
+    /// A missing bound leaves that side of the range open.
+    pub fn get_range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+    ) -> impl Iterator<Item = (&'a [u8], &'a [u8])> + 'a {
+        let empty = matches!((start, end), (Some(start), Some(end)) if start >= end);
+        let start = start.map_or(Bound::Unbounded, Bound::Included);
+        let end = end.map_or(Bound::Unbounded, Bound::Excluded);
+
+        (!empty)
+            .then(|| self.state.range::<[u8], _>((start, end)))
+            .into_iter()
+            .flatten()
+            .map(|(key, value)| (key.as_slice(), value.as_slice()))
+    }
+
+    /// Iterates over the keys starting with `prefix` in ascending order.
+    pub fn get_by_prefix<'a>(&'a self, prefix: &'a [u8]) -> impl Iterator<Item = (&'a [u8], &'a [u8])> + 'a {
+        self.get_range(Some(prefix), None)
+            .take_while(move |(key, _)| key.starts_with(prefix))
+    }
+
+    pub fn get_balance(&self, account: &Address) -> u64 {
+        self.balances.get(account).copied().unwrap_or(0)
+    }
+
+    pub fn set_balance(&mut self, account: Address, balance: u64) {
+        self.balances.insert(account, balance);
+    }
+
+    /// Reads `key` from the state of `contract`, as written by calls through [`Simulator::call_contract`].
+    pub fn get_contract_value(&self, contract: &Address, key: &[u8]) -> Option<&Vec<u8>> {
+        self.state.get(&Self::contract_key(contract, key))
+    }
+
+    /// The key that `contract` stores `key` under.
+    pub(crate) fn contract_key(contract: &Address, key: &[u8]) -> Vec<u8> {
+        [contract.as_bytes(), key].concat()
+    }
+
+    pub fn get_contract(&self, contract: &Address) -> Option<&Vec<u8>> {
+        self.contracts.get(contract)
+    }
+
+    pub fn set_contract(&mut self, contract: Address, code: Vec<u8>) {
+        self.contracts.insert(contract, code);
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn set_height(&mut self, height: u64) {
+        self.height = height;
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+
+    /// Writes the storage, balances, contracts, deploy nonces, and block to `path`, replacing the file if it exists.
+    /// The file starts with a format version byte followed by the borsh-encoded maps and block,
+    /// with every map sorted by key so that equal states produce identical files.
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> Result<(), SimulatorError> {
+        let balances: BTreeMap<_, _> = self
+            .balances
+            .iter()
+            .map(|(account, balance)| (account.into_bytes(), *balance))
+            .collect();
+        let contracts: BTreeMap<_, _> = self
+            .contracts
+            .iter()
+            .map(|(contract, code)| (contract.into_bytes(), code))
+            .collect();
+        let deploy_nonces: BTreeMap<_, _> = self
+            .deploy_nonces
+            .iter()
+            .map(|(deployer, nonce)| (deployer.into_bytes(), *nonce))
+            .collect();
+
+        let mut bytes = vec![STATE_FORMAT_VERSION];
+        let block = (self.height, self.timestamp);
+        borsh::to_writer(&mut bytes, &(&self.state, balances, contracts, deploy_nonces, block))?;
+        std::fs::write(path, bytes)?;
+
+        Ok(())
+    }
+
+    /// Reads a state written by [`SimulatorState::save_to_file`].
+    /// Returns [`SimulatorError::Corrupt`] if the file has a different format version or cannot be decoded.
+    pub fn load_from_file(path: impl AsRef<Path>) -> Result<Self, SimulatorError> {
+        let bytes = std::fs::read(path)?;
+
+        let Some((&version, bytes)) = bytes.split_first() else {
+            return Err(SimulatorError::Corrupt("empty file".to_string()));
+        };
+
+        if version != STATE_FORMAT_VERSION {
+            return Err(SimulatorError::Corrupt(format!(
+                "unsupported format version {version}, expected {STATE_FORMAT_VERSION}"
+            )));
+        }
+
+        type Contents = (
+            BTreeMap<Vec<u8>, Vec<u8>>,
+            BTreeMap<[u8; ADDRESS_LEN], u64>,
+            BTreeMap<[u8; ADDRESS_LEN], Vec<u8>>,
+            BTreeMap<[u8; ADDRESS_LEN], u64>,
+            (u64, u64),
+        );
+        let (state, balances, contracts, deploy_nonces, (height, timestamp)): Contents =
+            borsh::from_slice(bytes).map_err(|err| SimulatorError::Corrupt(err.to_string()))?;
+
+        Ok(Self {
+            state,
+            balances: balances.into_iter().map(|(account, balance)| (account.into(), balance)).collect(),
+            contracts: contracts.into_iter().map(|(contract, code)| (contract.into(), code)).collect(),
+            deploy_nonces: deploy_nonces.into_iter().map(|(deployer, nonce)| (deployer.into(), nonce)).collect(),
+            height,
+            timestamp,
+            events: Vec::new(),
+        })
+    }
+
+    /// Captures the storage, balances, contracts, and block so they can be [`restore`](Self::restore)d later.
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot(Arc::new(self.clone()))
+    }
+
+    /// Replaces everything with the contents of `snapshot`.
+    pub fn restore(&mut self, snapshot: StateSnapshot) {
+        *self = Arc::unwrap_or_clone(snapshot.0);
     }
 }
 
+/// Methods emulated by [`Simulator::execute_wasm`] when no code is given.
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+const STUB_EXPORTS: &[&str] = &["add"];
+
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
-#[derive(Default)]
 pub struct Simulator {
     state: Arc<RwLock<SimulatorState>>,
+    engine: wasmtime::Engine,
+    last_gas_used: AtomicU64,
+    /// The account contracts send balances from.
+    actor: RwLock<Address>,
+    deploy_policy: RwLock<DeployPolicy>,
+    memory_limit: AtomicUsize,
+    timeout: RwLock<Option<Duration>>,
+    /// Advances the engine's epoch once a timeout has been set.
+    ticker: OnceLock<timeout::Ticker>,
+    invariants: RwLock<invariants::Invariants>,
+    /// How many calls have been made, numbering them for [`InvariantViolation::call_index`].
+    calls: AtomicU64,
+    names: RwLock<names::Names>,
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
 impl Simulator {
     pub fn new() -> Self {
-        Self {
-            state: Arc::new(RwLock::new(SimulatorState::new())),
-        }
+        Self::with_state(Arc::new(RwLock::new(SimulatorState::new())))
     }
 
     pub fn with_state(state: Arc<RwLock<SimulatorState>>) -> Self {
-        Self { state }
+        Self {
+            state,
+            engine: runtime::engine(),
+            last_gas_used: AtomicU64::new(0),
+            actor: RwLock::new(Address::new([0; ADDRESS_LEN])),
+            deploy_policy: RwLock::default(),
+            memory_limit: AtomicUsize::new(DEFAULT_MEMORY_LIMIT),
+            timeout: RwLock::default(),
+            ticker: OnceLock::new(),
+            invariants: RwLock::default(),
+            calls: AtomicU64::new(0),
+            names: RwLock::default(),
+        }
     }
 
     pub fn get_state(&self) -> Arc<RwLock<SimulatorState>> {
         self.state.clone()
     }
 
-    pub fn execute_wasm(&self, _code: &[u8], method: &str, params: &[u8], _gas: u64) -> Result<Vec<u8>, SimulatorError> {
+    pub fn get_actor(&self) -> Address {
+        *self.actor.read().unwrap()
+    }
+
+    /// Sets the account that the `balance.send` import debits.
+    pub fn set_actor(&self, actor: Address) {
+        *self.actor.write().unwrap() = actor;
+    }
+
+    pub fn memory_limit(&self) -> usize {
+        self.memory_limit.load(Ordering::Relaxed)
+    }
+
+    /// Limits how many bytes of linear memory each contract in a call may use, [`DEFAULT_MEMORY_LIMIT`] by default.
+    /// Contracts that grow their memory past it fail with [`SimulatorError::MemoryLimitExceeded`].
+    pub fn set_memory_limit(&self, bytes: usize) {
+        self.memory_limit.store(bytes, Ordering::Relaxed);
+    }
+
+    /// Gas consumed by the most recent [`Simulator::execute_wasm`] or [`Simulator::call_contract`],
+    /// including failed calls.
+    pub fn last_gas_used(&self) -> u64 {
+        self.last_gas_used.load(Ordering::Relaxed)
+    }
+
+    /// Calls `method` on the contract `code`, allowing it to consume at most `gas` units of fuel.
+    /// Returns [`SimulatorError::OutOfGas`] if the limit is reached, in which case
+    /// [`Simulator::last_gas_used`] is `gas`.
+    /// A call that fails, for whatever reason, leaves the state as it was before the call:
+    /// none of its writes, balance transfers or events are kept, even those made before it failed.
+    ///
+    /// Empty `code` runs the emulated `add` method instead, which consumes no gas.
+    pub fn execute_wasm(&self, code: &[u8], method: &str, params: &[u8], gas: u64) -> Result<Vec<u8>, SimulatorError> {
+        if code.is_empty() {
+            self.last_gas_used.store(0, Ordering::Relaxed);
+            return Self::execute_emulated(method, params);
+        }
+
+        self.run(None, code, method, params, gas)
+    }
+
+    /// Calls `method` on the contract deployed at `contract` with [`Simulator::create_contract`].
+    /// Unlike [`Simulator::execute_wasm`], the contract's state is kept apart from every other contract's,
+    /// see [`SimulatorState::get_contract_value`].
+    pub fn call_contract(&self, contract: Address, method: &str, params: &[u8], gas: u64) -> Result<Vec<u8>, SimulatorError> {
+        let code = self
+            .state
+            .read()
+            .unwrap()
+            .get_contract(&contract)
+            .cloned()
+            .ok_or(SimulatorError::ContractNotFound(contract))?;
+
+        self.run(Some(contract), &code, method, params, gas)
+    }
+
+    fn run(&self, contract: Option<Address>, code: &[u8], method: &str, params: &[u8], gas: u64) -> Result<Vec<u8>, SimulatorError> {
+        let call_data = runtime::CallData::new(
+            self.state.clone(),
+            self.get_actor(),
+            contract,
+            self.memory_limit(),
+            self.timeout().map(|timeout| Instant::now() + timeout),
+        );
+        let call_index = self.calls.fetch_add(1, Ordering::Relaxed);
+        let snapshot = self.snapshot();
+        let outcome = runtime::call(&self.engine, call_data, code, method, params, gas);
+        self.last_gas_used.store(outcome.gas_used, Ordering::Relaxed);
+        if outcome.result.is_err() {
+            self.restore(snapshot);
+        }
+        self.check_invariants(Some(call_index))?;
+        outcome.result
+    }
+
+    /// Like [`Simulator::execute_wasm`], but borsh-encodes `params` and decodes the result as `R`,
+    /// the same way `#[public]` functions encode their arguments and return values.
+    /// Returns [`SimulatorError::Decode`] if the result is not a valid `R`.
+    pub fn execute_typed<P, R>(&self, code: &[u8], method: &str, params: &P, gas: u64) -> Result<R, SimulatorError>
+    where
+        P: BorshSerialize,
+        R: BorshDeserialize,
+    {
+        let params = borsh::to_vec(params)?;
+        let result = self.execute_wasm(code, method, &params, gas)?;
+        borsh::from_slice(&result).map_err(SimulatorError::Decode)
+    }
+
+    fn execute_emulated(method: &str, params: &[u8]) -> Result<Vec<u8>, SimulatorError> {
         // For now, we'll simulate the add function
         if method == "add" {
             let params_str = std::str::from_utf8(params)?;
@@ -109,29 +610,122 @@ impl Simulator {
             // Return as uint64 in little-endian format
             Ok(result_u64.to_le_bytes().to_vec())
         } else {
-            Err(SimulatorError::ContractExecution(format!("Function '{}' not found in contract", method)))
+            Err(SimulatorError::method_not_found(method, STUB_EXPORTS.iter().copied()))
         }
     }
 
     pub fn get_balance(&self, account: Address) -> u64 {
         let state = self.state.read().unwrap();
-        state.get_value(account.as_bytes())
-            .map(|v| {
-                let mut bytes = [0u8; 8];
-                bytes.copy_from_slice(&v[..8]);
-                u64::from_le_bytes(bytes)
-            })
-            .unwrap_or(0)
+        state.get_balance(&account)
     }
 
     pub fn set_balance(&self, account: Address, balance: u64) {
         let mut state = self.state.write().unwrap();
-        state.set_value(account.as_bytes().to_vec(), balance.to_le_bytes().to_vec());
+        state.set_balance(account, balance);
     }
 
-    pub fn create_contract(&self, contract: Address, code: Vec<u8>) {
+    /// Stores `code` at `contract`, replacing any code already there.
+    /// Returns [`SimulatorError::DeployForbidden`] if the [`DeployPolicy`] does not let the actor deploy.
+    pub fn create_contract(&self, contract: Address, code: Vec<u8>) -> Result<(), SimulatorError> {
+        self.check_deploy()?;
         let mut state = self.state.write().unwrap();
-        state.set_value(contract.as_bytes().to_vec(), code);
+        state.set_contract(contract, code);
+        Ok(())
+    }
+
+    /// Reads a contract from `path` and stores it at `contract` like [`Simulator::create_contract`].
+    /// Files ending in `.wat` are compiled from the text format first.
+    ///
+    /// Returns [`SimulatorError::InvalidWasm`] if the file is not a valid wasm module
+    /// or is larger than [`MAX_CONTRACT_SIZE`].
+    pub fn create_contract_from_file(&self, contract: Address, path: impl AsRef<Path>) -> Result<(), SimulatorError> {
+        let code = self.read_contract(path.as_ref())?;
+        self.create_contract(contract, code)
+    }
+
+    /// Reads a contract from `path` like [`Simulator::create_contract_from_file`] and calls `method` on it
+    /// like [`Simulator::execute_wasm`].
+    pub fn execute_file(
+        &self,
+        path: impl AsRef<Path>,
+        method: &str,
+        params: &[u8],
+        gas: u64,
+    ) -> Result<Vec<u8>, SimulatorError> {
+        let code = self.read_contract(path.as_ref())?;
+        self.execute_wasm(&code, method, params, gas)
+    }
+
+    fn read_contract(&self, path: &Path) -> Result<Vec<u8>, SimulatorError> {
+        let invalid = |reason: String| SimulatorError::InvalidWasm {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let bytes = std::fs::read(path)
+            .map_err(|err| std::io::Error::new(err.kind(), format!("{}: {err}", path.display())))?;
+        let code = if path.extension().is_some_and(|extension| extension == "wat") {
+            wat::parse_bytes(&bytes).map_err(|err| invalid(err.to_string()))?.into_owned()
+        } else {
+            bytes
+        };
+
+        if code.len() > MAX_CONTRACT_SIZE {
+            return Err(invalid(format!(
+                "{} bytes is more than the maximum of {MAX_CONTRACT_SIZE}",
+                code.len()
+            )));
+        }
+        if !code.starts_with(WASM_MAGIC) {
+            return Err(invalid("missing the `\\0asm` magic bytes".to_string()));
+        }
+        wasmtime::Module::validate(&self.engine, &code).map_err(|err| invalid(format!("{err:#}")))?;
+
+        Ok(code)
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.state.read().unwrap().get_height()
+    }
+
+    pub fn set_height(&self, height: u64) {
+        self.state.write().unwrap().set_height(height);
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.state.read().unwrap().get_timestamp()
+    }
+
+    pub fn set_timestamp(&self, timestamp: u64) {
+        self.state.write().unwrap().set_timestamp(timestamp);
+    }
+
+    /// Moves on to the next block, `seconds` after the current one.
+    /// Returns [`SimulatorError::BlockOverflow`], leaving the block as it was, if the height or timestamp
+    /// would not fit in a `u64`, and [`SimulatorError::InvariantViolated`] if the new block breaks an
+    /// invariant, see [`Simulator::register_invariant`].
+    pub fn advance_block(&self, seconds: u64) -> Result<(), SimulatorError> {
+        {
+            let mut state = self.state.write().unwrap();
+            let (Some(height), Some(timestamp)) =
+                (state.height.checked_add(1), state.timestamp.checked_add(seconds))
+            else {
+                return Err(SimulatorError::BlockOverflow);
+            };
+            state.height = height;
+            state.timestamp = timestamp;
+        }
+        self.check_invariants(None)
+    }
+
+    /// See [`SimulatorState::snapshot`].
+    pub fn snapshot(&self) -> StateSnapshot {
+        self.state.read().unwrap().snapshot()
+    }
+
+    /// See [`SimulatorState::restore`].
+    pub fn restore(&self, snapshot: StateSnapshot) {
+        self.state.write().unwrap().restore(snapshot);
     }
 }
 
@@ -149,22 +743,1071 @@ mod tests {
     #[test]
     fn test_contract_creation() {
         let simulator = Simulator::new();
-        let contract = Address::new(vec![1, 2, 3]);
+        let contract = Address::new([1; ADDRESS_LEN]);
         let code = vec![4, 5, 6];
-        simulator.create_contract(contract.clone(), code.clone());
+        simulator.create_contract(contract, code.clone()).unwrap();
         
         let state = simulator.get_state();
-        assert_eq!(state.read().unwrap().get_value(&contract.as_bytes()).unwrap(), &code);
+        assert_eq!(state.read().unwrap().get_contract(&contract).unwrap(), &code);
     }
 
     #[test]
     fn test_balance() {
         let simulator = Simulator::new();
-        let account = Address::new(vec![1, 2, 3]);
+        let account = Address::new([1; ADDRESS_LEN]);
         let balance = 100;
-        simulator.set_balance(account.clone(), balance);
+        simulator.set_balance(account, balance);
         assert_eq!(simulator.get_balance(account), balance);
     }
+
+    #[test]
+    fn address_try_from_validates_length() {
+        let address = Address::try_from(vec![7; ADDRESS_LEN]).unwrap();
+        assert_eq!(address.into_bytes(), [7; ADDRESS_LEN]);
+
+        for len in [0, 3, 32, 34] {
+            let err = Address::try_from(vec![7; len]).unwrap_err();
+            assert!(matches!(err, SimulatorError::InvalidAddress(_)));
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_vec_conversions() {
+        let address = Address::from_vec(vec![7; ADDRESS_LEN]);
+        assert_eq!(address, Address::new([7; ADDRESS_LEN]));
+        assert_eq!(address.to_vec(), vec![7; ADDRESS_LEN]);
+
+        let short = std::panic::catch_unwind(|| Address::from_vec(vec![7; 3]));
+        assert!(short.is_err());
+    }
+
+    #[test]
+    fn address_parses_hex() {
+        let address = Address::new(core::array::from_fn(|i| i as u8 * 7));
+        let hex = address.to_hex();
+
+        assert_eq!(hex.len(), ADDRESS_LEN * 2);
+        assert!(hex.starts_with("00070e15"));
+        assert_eq!(hex.parse::<Address>().unwrap(), address);
+        assert_eq!(format!("0x{address}").parse::<Address>().unwrap(), address);
+        assert_eq!(hex.to_uppercase().parse::<Address>().unwrap(), address);
+    }
+
+    #[test]
+    fn address_rejects_invalid_hex() {
+        let valid = "ab".repeat(ADDRESS_LEN);
+
+        for s in [
+            "",
+            "0x",
+            "0xabc123",
+            &valid[2..],
+            &format!("{valid}ab"),
+            &format!("{}zz", &valid[2..]),
+            &format!("{}+f", &valid[2..]),
+            &valid[1..],
+            &format!("0X{valid}"),
+        ] {
+            let err = s.parse::<Address>().unwrap_err();
+            assert!(matches!(err, SimulatorError::InvalidAddress(_)), "{s}: {err}");
+        }
+    }
+
+    const SPIN: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "spin") (param i32)
+                (loop $forever (br $forever))))
+    "#;
+
+    const ECHO: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 16)
+            (func (export "echo") (param $ptr i32)
+                (call $set_call_result (local.get $ptr) (i32.const 4))))
+    "#;
+
+    #[test]
+    fn unbounded_loop_runs_out_of_gas() {
+        let simulator = Simulator::new();
+        let err = simulator
+            .execute_wasm(SPIN.as_bytes(), "spin", &[], 10_000)
+            .unwrap_err();
+
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+        assert_eq!(simulator.last_gas_used(), 10_000);
+    }
+
+    // puts `key!` => `abc` and then spins until it runs out of gas
+    const PUT_THEN_SPIN: &str = r#"
+        (module
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `vec![(b"key!", b"abc")]`
+            (data (i32.const 0) "\01\00\00\00\04\00\00\00key!\03\00\00\00abc")
+            (func (export "put_then_spin") (param i32)
+                (call $put (i32.const 0) (i32.const 19))
+                (loop $forever (br $forever))))
+    "#;
+
+    #[test]
+    fn failed_calls_keep_none_of_their_writes() {
+        let simulator = Simulator::new();
+        let err = simulator
+            .execute_wasm(PUT_THEN_SPIN.as_bytes(), "put_then_spin", &[], 10_000)
+            .unwrap_err();
+
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+        assert!(simulator.get_state().read().unwrap().get_value(b"key!").is_none());
+    }
+
+    // puts `keyA` => `a`, burns gas counting to 100,000, then puts `keyB` => `b`
+    const WRITE_BURN_WRITE: &str = r#"
+        (module
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `vec![(b"keyA", b"a")]` and `vec![(b"keyB", b"b")]`
+            (data (i32.const 0) "\01\00\00\00\04\00\00\00keyA\01\00\00\00a")
+            (data (i32.const 32) "\01\00\00\00\04\00\00\00keyB\01\00\00\00b")
+            (func (export "write_burn_write") (param i32)
+                (local $i i32)
+                (call $put (i32.const 0) (i32.const 17))
+                (loop $burn
+                    (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                    (br_if $burn (i32.lt_u (local.get $i) (i32.const 100000))))
+                (call $put (i32.const 32) (i32.const 17))))
+    "#;
+
+    #[test]
+    fn running_out_of_gas_between_writes_keeps_neither() {
+        let simulator = Simulator::new();
+        let err = simulator
+            .execute_wasm(WRITE_BURN_WRITE.as_bytes(), "write_burn_write", &[], 50_000)
+            .unwrap_err();
+
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+        assert_eq!(simulator.last_gas_used(), 50_000);
+        let state = simulator.get_state();
+        assert!(state.read().unwrap().get_value(b"keyA").is_none());
+        assert!(state.read().unwrap().get_value(b"keyB").is_none());
+
+        // with enough gas both writes are kept
+        simulator
+            .execute_wasm(WRITE_BURN_WRITE.as_bytes(), "write_burn_write", &[], 10_000_000)
+            .unwrap();
+        assert_eq!(state.read().unwrap().get_value(b"keyA"), Some(&b"a".to_vec()));
+        assert_eq!(state.read().unwrap().get_value(b"keyB"), Some(&b"b".to_vec()));
+    }
+
+    const GROW: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "grow_once") (param i32)
+                (drop (memory.grow (i32.const 1))))
+            ;; grows until `memory.grow` fails
+            (func (export "grow_forever") (param i32)
+                (loop $more
+                    (br_if $more (i32.ne (memory.grow (i32.const 1)) (i32.const -1))))))
+    "#;
+
+    const PAGE_SIZE: usize = 64 * 1024;
+
+    #[test]
+    fn unbounded_memory_growth_hits_limit() {
+        let simulator = Simulator::new();
+        assert_eq!(simulator.memory_limit(), DEFAULT_MEMORY_LIMIT);
+
+        let err = simulator
+            .execute_wasm(GROW.as_bytes(), "grow_forever", &[], 10_000_000)
+            .unwrap_err();
+
+        assert!(matches!(err, SimulatorError::MemoryLimitExceeded), "{err}");
+    }
+
+    #[test]
+    fn memory_limit_is_configurable() {
+        let simulator = Simulator::new();
+        simulator.set_memory_limit(2 * PAGE_SIZE);
+        simulator
+            .execute_wasm(GROW.as_bytes(), "grow_once", &[], 10_000)
+            .unwrap();
+
+        simulator.set_memory_limit(PAGE_SIZE);
+        let err = simulator
+            .execute_wasm(GROW.as_bytes(), "grow_once", &[], 10_000)
+            .unwrap_err();
+        assert!(matches!(err, SimulatorError::MemoryLimitExceeded), "{err}");
+    }
+
+    #[test]
+    fn gas_used_is_reported() {
+        let simulator = Simulator::new();
+        let result = simulator
+            .execute_wasm(ECHO.as_bytes(), "echo", &[1, 2, 3, 4], 100_000)
+            .unwrap();
+
+        assert_eq!(result, [1, 2, 3, 4]);
+        let gas_used = simulator.last_gas_used();
+        assert!(gas_used > SET_RESULT_COST && gas_used < 100_000, "{gas_used}");
+    }
+
+    #[test]
+    fn call_result_is_always_returned() {
+        let simulator = Simulator::new();
+
+        for i in 0..100u8 {
+            let params = [i, i + 1, i + 2, i + 3];
+            let result = simulator
+                .execute_wasm(ECHO.as_bytes(), "echo", &params, 100_000)
+                .unwrap();
+            assert_eq!(result, params);
+        }
+    }
+
+    // puts `key!` => `abc`, deletes it, and returns the deleted value if `get` no longer finds it
+    const SET_DELETE_GET: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "state" "put" (func $put (param i32 i32)))
+            (import "state" "get" (func $get (param i32 i32) (result i32)))
+            (import "state" "delete" (func $delete (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            ;; borsh `vec![(b"key!", b"abc")]`
+            (data (i32.const 0) "\01\00\00\00\04\00\00\00key!\03\00\00\00abc")
+            ;; borsh `GetArgs { key: b"key!" }`, taken by both `delete` and `get`
+            (data (i32.const 32) "\04\00\00\00key!")
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "set_delete_get") (param i32)
+                (local $previous i32)
+                (call $put (i32.const 0) (i32.const 19))
+                (local.set $previous (call $delete (i32.const 32) (i32.const 8)))
+                (if (call $get (i32.const 32) (i32.const 8)) (then unreachable))
+                (call $set_call_result (local.get $previous) (i32.const 3))))
+    "#;
+
+    #[test]
+    fn state_delete_through_wasm() {
+        let simulator = Simulator::new();
+        let result = simulator
+            .execute_wasm(SET_DELETE_GET.as_bytes(), "set_delete_get", &[], 100_000)
+            .unwrap();
+
+        assert_eq!(result, b"abc");
+        assert!(simulator.get_state().read().unwrap().get_value(b"key!").is_none());
+    }
+
+    #[test]
+    fn writes_and_results_charge_flat_costs() {
+        let simulator = Simulator::new();
+        simulator
+            .execute_wasm(SET_DELETE_GET.as_bytes(), "set_delete_get", &[], 100_000)
+            .unwrap();
+        assert!(simulator.last_gas_used() > STATE_WRITE_COST + SET_RESULT_COST);
+
+        let err = simulator
+            .execute_wasm(SET_DELETE_GET.as_bytes(), "set_delete_get", &[], STATE_WRITE_COST)
+            .unwrap_err();
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+    }
+
+    // returns the value at `key!`, using the size the host requested from `alloc` as its length
+    const GET_LARGE: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "state" "get" (func $get (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $last_len (mut i32) (i32.const 0))
+            ;; borsh `GetArgs { key: b"key!" }`
+            (data (i32.const 0) "\04\00\00\00key!")
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.set $last_len (local.get $len))
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "get_large") (param i32)
+                (local $value i32)
+                (local.set $value (call $get (i32.const 0) (i32.const 8)))
+                (call $set_call_result (local.get $value) (global.get $last_len))))
+    "#;
+
+    #[test]
+    fn state_get_returns_values_longer_than_the_key() {
+        let simulator = Simulator::new();
+        let value: Vec<u8> = (0..100).collect();
+        simulator
+            .get_state()
+            .write()
+            .unwrap()
+            .set_value(b"key!".to_vec(), value.clone());
+
+        let result = simulator
+            .execute_wasm(GET_LARGE.as_bytes(), "get_large", &[], 100_000)
+            .unwrap();
+
+        assert_eq!(result, value);
+    }
+
+    const PUT: &str = r#"
+        (module
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `vec![(b"key!", b"abc")]`
+            (data (i32.const 0) "\01\00\00\00\04\00\00\00key!\03\00\00\00abc")
+            (func (export "put") (param i32)
+                (call $put (i32.const 0) (i32.const 19))))
+    "#;
+
+    #[test]
+    fn restore_rolls_back_everything() {
+        let simulator = Simulator::new();
+        let account = Address::new([1; ADDRESS_LEN]);
+        let contract = Address::new([2; ADDRESS_LEN]);
+        simulator.set_balance(account, 10);
+
+        let snapshot = simulator.snapshot();
+
+        simulator.set_balance(account, 20);
+        simulator.create_contract(contract, PUT.as_bytes().to_vec()).unwrap();
+        simulator
+            .execute_wasm(PUT.as_bytes(), "put", &[], 100_000)
+            .unwrap();
+        assert_eq!(
+            simulator.get_state().read().unwrap().get_value(b"key!"),
+            Some(&b"abc".to_vec())
+        );
+
+        simulator.restore(snapshot.clone());
+
+        let state = simulator.get_state();
+        let state = state.read().unwrap();
+        assert_eq!(state.get_balance(&account), 10);
+        assert!(state.get_value(b"key!").is_none());
+        assert!(state.get_contract(&contract).is_none());
+        drop(state);
+
+        // a snapshot can be restored more than once
+        simulator.set_balance(account, 30);
+        simulator.restore(snapshot);
+        assert_eq!(simulator.get_balance(account), 10);
+    }
+
+    /// A path in the temporary directory that is removed when dropped.
+    struct TempPath(std::path::PathBuf);
+
+    impl TempPath {
+        fn new(name: &str) -> Self {
+            Self(std::env::temp_dir().join(format!("simulator-{}-{name}", std::process::id())))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn contracts_load_from_files() {
+        let simulator = Simulator::new();
+        let contract = Address::new([1; ADDRESS_LEN]);
+        let (wat, wasm) = (TempPath::new("counter.wat"), TempPath::new("counter.wasm"));
+        std::fs::write(&wat.0, COUNTER).unwrap();
+        std::fs::write(&wasm.0, wat::parse_str(COUNTER).unwrap()).unwrap();
+
+        for path in [&wat.0, &wasm.0] {
+            simulator.create_contract_from_file(contract, path).unwrap();
+            simulator.call_contract(contract, "increment", &[], 100_000).unwrap();
+            simulator.execute_file(path, "increment", &[], 100_000).unwrap();
+        }
+
+        let state = simulator.get_state();
+        let state = state.read().unwrap();
+        assert_eq!(state.get_contract_value(&contract, b"count"), Some(&vec![2]));
+        assert_eq!(state.get_value(b"count"), Some(&vec![2]));
+    }
+
+    #[test]
+    fn invalid_contract_files() {
+        let simulator = Simulator::new();
+        let contract = Address::new([1; ADDRESS_LEN]);
+        let path = TempPath::new("truncated.wasm");
+        let wasm = wat::parse_str(COUNTER).unwrap();
+
+        let load = |bytes: &[u8]| {
+            std::fs::write(&path.0, bytes).unwrap();
+            simulator.create_contract_from_file(contract, &path.0).unwrap_err()
+        };
+
+        for bytes in [&wasm[..wasm.len() / 2], b"not wasm", &vec![0; MAX_CONTRACT_SIZE + 1]] {
+            let err = load(bytes);
+            assert!(matches!(&err, SimulatorError::InvalidWasm { path: p, .. } if *p == path.0), "{err}");
+            assert!(err.to_string().contains(&path.0.display().to_string()), "{err}");
+        }
+        assert!(simulator.get_state().read().unwrap().get_contract(&contract).is_none());
+
+        let missing = TempPath::new("missing.wasm");
+        let err = simulator.execute_file(&missing.0, "increment", &[], 100_000).unwrap_err();
+        assert!(matches!(err, SimulatorError::Io(_)), "{err}");
+        assert!(err.to_string().contains("missing.wasm"), "{err}");
+    }
+
+    #[test]
+    fn state_file_round_trips() {
+        let mut state = SimulatorState::new();
+        for i in 0..10u8 {
+            state.set_value(vec![i; i as usize + 1], vec![i; 100]);
+            state.set_balance(Address::new([i; ADDRESS_LEN]), u64::from(i) * 1000);
+            state.set_contract(Address::new([i + 100; ADDRESS_LEN]), vec![i; 64]);
+        }
+        state.set_height(7);
+        state.set_timestamp(1_700_000_000);
+        state.next_deploy_nonce(Address::new([9; ADDRESS_LEN]));
+
+        let (path, copy_path) = (TempPath::new("round-trip"), TempPath::new("round-trip-copy"));
+        state.save_to_file(&path.0).unwrap();
+
+        let loaded = SimulatorState::load_from_file(&path.0).unwrap();
+        assert_eq!(loaded, state);
+
+        // saving is deterministic even though the maps are unordered
+        loaded.save_to_file(&copy_path.0).unwrap();
+        assert_eq!(std::fs::read(&path.0).unwrap(), std::fs::read(&copy_path.0).unwrap());
+    }
+
+    #[test]
+    fn state_file_with_other_version_is_rejected() {
+        let path = TempPath::new("other-version");
+        SimulatorState::new().save_to_file(&path.0).unwrap();
+
+        let mut bytes = std::fs::read(&path.0).unwrap();
+        bytes[0] = STATE_FORMAT_VERSION + 1;
+        std::fs::write(&path.0, bytes).unwrap();
+
+        let err = SimulatorState::load_from_file(&path.0).unwrap_err();
+        assert!(matches!(&err, SimulatorError::Corrupt(message) if message.contains("version")), "{err}");
+    }
+
+    #[test]
+    fn truncated_state_file_is_rejected() {
+        let path = TempPath::new("truncated");
+        let mut state = SimulatorState::new();
+        state.set_value(b"key".to_vec(), b"value".to_vec());
+        state.save_to_file(&path.0).unwrap();
+
+        let bytes = std::fs::read(&path.0).unwrap();
+        std::fs::write(&path.0, &bytes[..bytes.len() - 1]).unwrap();
+
+        let err = SimulatorState::load_from_file(&path.0).unwrap_err();
+        assert!(matches!(err, SimulatorError::Corrupt(_)), "{err}");
+
+        let missing = TempPath::new("missing");
+        let err = SimulatorState::load_from_file(&missing.0).unwrap_err();
+        assert!(matches!(err, SimulatorError::Io(_)), "{err}");
+    }
+
+    #[test]
+    fn delete_value_returns_previous() {
+        let mut state = SimulatorState::new();
+        state.set_value(b"key".to_vec(), b"value".to_vec());
+
+        assert_eq!(state.delete_value(b"key"), Some(b"value".to_vec()));
+        assert_eq!(state.delete_value(b"key"), None);
+        assert!(state.get_value(b"key").is_none());
+    }
+
+    #[test]
+    fn missing_export_suggests_method() {
+        let simulator = Simulator::new();
+        let err = simulator
+            .execute_wasm(ECHO.as_bytes(), "ecko", &[], 10_000)
+            .unwrap_err();
+
+        let SimulatorError::MethodNotFound { did_you_mean, available, .. } = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(did_you_mean.as_deref(), Some("echo"));
+        assert_eq!(available, ["echo"]);
+    }
+
+    #[test]
+    fn unknown_method_suggests_export() {
+        let simulator = Simulator::new();
+        let err = simulator.execute_wasm(&[], "ad", b"1,2", 0).unwrap_err();
+
+        let SimulatorError::MethodNotFound { did_you_mean, .. } = &err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(did_you_mean.as_deref(), Some("add"));
+        assert_eq!(
+            err.to_string(),
+            "Function 'ad' not found in contract; did you mean 'add'? (available: add)"
+        );
+    }
+
+    // takes two borsh `i64`s
+    const ARITHMETIC: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 16)
+            ;; returns `a + b`
+            (func (export "add") (param $ptr i32)
+                (i64.store (i32.const 64)
+                    (i64.add (i64.load (local.get $ptr)) (i64.load offset=8 (local.get $ptr))))
+                (call $set_call_result (i32.const 64) (i32.const 8)))
+            ;; returns `MinMax { min, max }`
+            (func (export "min_max") (param $ptr i32)
+                (local $a i64) (local $b i64)
+                (local.set $a (i64.load (local.get $ptr)))
+                (local.set $b (i64.load offset=8 (local.get $ptr)))
+                (i64.store (i32.const 64)
+                    (select (local.get $a) (local.get $b) (i64.lt_s (local.get $a) (local.get $b))))
+                (i64.store (i32.const 72)
+                    (select (local.get $b) (local.get $a) (i64.lt_s (local.get $a) (local.get $b))))
+                (call $set_call_result (i32.const 64) (i32.const 16))))
+    "#;
+
+    #[derive(Debug, PartialEq, BorshDeserialize)]
+    struct MinMax {
+        min: i64,
+        max: i64,
+    }
+
+    #[test]
+    fn execute_typed_decodes_primitives() {
+        let simulator = Simulator::new();
+        let sum: i64 = simulator
+            .execute_typed(ARITHMETIC.as_bytes(), "add", &(-7i64, 3i64), 100_000)
+            .unwrap();
+
+        assert_eq!(sum, -4);
+    }
+
+    #[test]
+    fn execute_typed_decodes_structs() {
+        let simulator = Simulator::new();
+        let result: MinMax = simulator
+            .execute_typed(ARITHMETIC.as_bytes(), "min_max", &(5i64, -2i64), 100_000)
+            .unwrap();
+
+        assert_eq!(result, MinMax { min: -2, max: 5 });
+    }
+
+    #[test]
+    fn execute_typed_rejects_mismatched_result() {
+        let simulator = Simulator::new();
+        let err = simulator
+            .execute_typed::<_, MinMax>(ARITHMETIC.as_bytes(), "add", &(1i64, 2i64), 100_000)
+            .unwrap_err();
+
+        assert!(matches!(err, SimulatorError::Decode(_)), "{err}");
+    }
+
+    const BLOCK: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "env" "height" (func $height (result i64)))
+            (import "env" "timestamp" (func $timestamp (result i64)))
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 16)
+            ;; returns `(height, timestamp)`
+            (func (export "block") (param i32)
+                (i64.store (i32.const 0) (call $height))
+                (i64.store (i32.const 8) (call $timestamp))
+                (call $set_call_result (i32.const 0) (i32.const 16))))
+    "#;
+
+    #[test]
+    fn block_is_visible_to_contracts() {
+        let simulator = Simulator::new();
+        let block = || -> (u64, u64) {
+            simulator
+                .execute_typed(BLOCK.as_bytes(), "block", &(), 100_000)
+                .unwrap()
+        };
+
+        assert_eq!(block(), (0, 0));
+
+        simulator.set_height(10);
+        simulator.set_timestamp(1_000);
+        assert_eq!(block(), (10, 1_000));
+
+        simulator.advance_block(12).unwrap();
+        assert_eq!(block(), (11, 1_012));
+        assert_eq!((simulator.get_height(), simulator.get_timestamp()), (11, 1_012));
+    }
+
+    #[test]
+    fn advancing_past_the_last_block_fails() {
+        let simulator = Simulator::new();
+        simulator.set_timestamp(1_000);
+
+        let err = simulator.advance_block(u64::MAX).unwrap_err();
+        assert!(matches!(err, SimulatorError::BlockOverflow), "{err}");
+        assert_eq!((simulator.get_height(), simulator.get_timestamp()), (0, 1_000));
+
+        simulator.set_height(u64::MAX);
+        let err = simulator.advance_block(1).unwrap_err();
+        assert!(matches!(err, SimulatorError::BlockOverflow), "{err}");
+        assert_eq!((simulator.get_height(), simulator.get_timestamp()), (u64::MAX, 1_000));
+    }
+
+    // counts its calls in state under `count`
+    const COUNTER: &str = r#"
+        (module
+            (import "state" "get" (func $get (param i32 i32) (result i32)))
+            (import "state" "put" (func $put (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `GetArgs { key: b"count" }`
+            (data (i32.const 0) "\05\00\00\00count")
+            ;; borsh `vec![(b"count", [n])]`, `n` is written at offset 49
+            (data (i32.const 32) "\01\00\00\00\05\00\00\00count\01\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "increment") (param i32)
+                (local $value i32)
+                (local.set $value (call $get (i32.const 0) (i32.const 9)))
+                (i32.store8 (i32.const 49)
+                    (if (result i32) (local.get $value)
+                        (then (i32.add (i32.load8_u (local.get $value)) (i32.const 1)))
+                        (else (i32.const 1))))
+                (call $put (i32.const 32) (i32.const 18))))
+    "#;
+
+    #[test]
+    fn contracts_have_separate_state() {
+        let simulator = Simulator::new();
+        let (a, b) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.create_contract(a, COUNTER.as_bytes().to_vec()).unwrap();
+        simulator.create_contract(b, COUNTER.as_bytes().to_vec()).unwrap();
+
+        for contract in [a, a, b, a] {
+            simulator.call_contract(contract, "increment", &[], 100_000).unwrap();
+        }
+
+        let state = simulator.get_state();
+        let state = state.read().unwrap();
+        assert_eq!(state.get_contract_value(&a, b"count"), Some(&vec![3]));
+        assert_eq!(state.get_contract_value(&b, b"count"), Some(&vec![1]));
+        assert!(state.get_value(b"count").is_none());
+    }
+
+    fn entries<'a>(iter: impl Iterator<Item = (&'a [u8], &'a [u8])>) -> Vec<(&'a [u8], &'a [u8])> {
+        iter.collect()
+    }
+
+    fn balances_state() -> SimulatorState {
+        let mut state = SimulatorState::new();
+        for key in [b"balance/b".as_slice(), b"balance/a", b"balancf", b"balance/c", b"allowance/a"] {
+            state.set_value(key.to_vec(), key[key.len() - 1..].to_vec());
+        }
+        state
+    }
+
+    #[test]
+    fn get_range_is_ascending() {
+        let state = balances_state();
+
+        let keys: Vec<_> = state.get_range(None, None).map(|(key, _)| key).collect();
+        assert_eq!(
+            keys,
+            [b"allowance/a".as_slice(), b"balance/a", b"balance/b", b"balance/c", b"balancf"]
+        );
+        assert_eq!(
+            entries(state.get_range(Some(b"balance/b"), Some(b"balancf"))),
+            [(b"balance/b".as_slice(), b"b".as_slice()), (b"balance/c", b"c")]
+        );
+    }
+
+    #[test]
+    fn get_by_prefix_filters() {
+        let state = balances_state();
+
+        assert_eq!(
+            entries(state.get_by_prefix(b"balance/")),
+            [(b"balance/a".as_slice(), b"a".as_slice()), (b"balance/b", b"b"), (b"balance/c", b"c")]
+        );
+        assert_eq!(state.get_by_prefix(b"").count(), 5);
+    }
+
+    #[test]
+    fn empty_ranges() {
+        let state = balances_state();
+
+        assert_eq!(state.get_range(Some(b"balance/b"), Some(b"balance/b")).count(), 0);
+        assert_eq!(state.get_range(Some(b"z"), Some(b"a")).count(), 0);
+        assert_eq!(state.get_range(Some(b"z"), None).count(), 0);
+        assert_eq!(state.get_by_prefix(b"nonce/").count(), 0);
+        assert_eq!(SimulatorState::new().get_range(None, None).count(), 0);
+    }
+
+    // `scan` passes its params on to `state.scan` and returns the page
+    const SCAN: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "state" "scan" (func $scan (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $last_len (mut i32) (i32.const 0))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.set $last_len (local.get $len))
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "scan") (param $ptr i32)
+                (local $page i32)
+                (local.set $page (call $scan (local.get $ptr) (global.get $last_len)))
+                (call $set_call_result (local.get $page) (global.get $last_len))))
+    "#;
+
+    #[test]
+    fn contracts_scan_their_own_prefix() {
+        type Page = (u32, Vec<(Vec<u8>, Vec<u8>)>);
+
+        let simulator = Simulator::new();
+        let (contract, other) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.create_contract(contract, SCAN.as_bytes().to_vec()).unwrap();
+        {
+            let state = simulator.get_state();
+            let mut state = state.write().unwrap();
+            for account in [b'c', b'a', b'b'] {
+                let key = [b"balance/".as_slice(), &[account]].concat();
+                state.set_value(SimulatorState::contract_key(&contract, &key), vec![account]);
+            }
+            state.set_value(SimulatorState::contract_key(&other, b"balance/z"), vec![0]);
+            state.set_value(b"balance/y".to_vec(), vec![0]);
+        }
+
+        let scan = |offset: u32, limit: u32| -> Page {
+            let args = borsh::to_vec(&(b"balance/".to_vec(), offset, limit)).unwrap();
+            let page = simulator.call_contract(contract, "scan", &args, 1_000_000).unwrap();
+            borsh::from_slice(&page).unwrap()
+        };
+
+        let entry = |account: u8| ([b"balance/".as_slice(), &[account]].concat(), vec![account]);
+        assert_eq!(scan(0, 2), (3, vec![entry(b'a'), entry(b'b')]));
+        assert_eq!(scan(2, 2), (3, vec![entry(b'c')]));
+        assert_eq!(scan(3, 2), (3, vec![]));
+    }
+
+    // `forward` passes its params on to `contract.call_contract` and returns the result,
+    // `leaf` returns `42i64`
+    const FORWARD: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "contract" "call_contract" (func $call_contract (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $last_len (mut i32) (i32.const 0))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.set $last_len (local.get $len))
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "forward") (param $ptr i32)
+                (local $result i32)
+                (local.set $result (call $call_contract (local.get $ptr) (global.get $last_len)))
+                (call $set_call_result (local.get $result) (global.get $last_len)))
+            (func (export "leaf") (param i32)
+                (i64.store (i32.const 0) (i64.const 42))
+                (call $set_call_result (i32.const 0) (i32.const 8))))
+    "#;
+
+    // `get_fuel` returns the gas it has left, like the `fuel` test contract
+    const FUEL: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "contract" "remaining_fuel" (func $remaining_fuel (result i32)))
+            (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "get_fuel") (param i32)
+                (call $set_call_result (call $remaining_fuel) (i32.const 8))))
+    "#;
+
+    #[test]
+    fn contracts_read_their_remaining_fuel() {
+        let simulator = Simulator::new();
+        let (caller, callee) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.create_contract(caller, FORWARD.as_bytes().to_vec()).unwrap();
+        simulator.create_contract(callee, FUEL.as_bytes().to_vec()).unwrap();
+
+        let result = simulator.call_contract(callee, "get_fuel", &[], 100_000).unwrap();
+        let remaining = u64::from_le_bytes(result.try_into().unwrap());
+        assert!(remaining < 100_000 && remaining > 100_000 - simulator.last_gas_used(), "{remaining}");
+
+        // a callee only has the `max_units` it was given
+        let call = |max_units: u64| {
+            let args = call_contract_args(callee, "get_fuel", &[], max_units, 0);
+            simulator.call_contract(caller, "forward", &args, 100_000).unwrap()
+        };
+
+        let result = call(50_000);
+        let remaining = u64::from_le_bytes(result[1..].try_into().unwrap());
+        assert!(remaining < 50_000 && remaining > 49_000, "{remaining}");
+
+        // `Err(ExternalCallError::OutOfFuel)`
+        assert_eq!(call(0), [1, 2]);
+    }
+
+    /// Borsh-encoded `CallContractArgs`.
+    fn call_contract_args(target: Address, method: &str, params: &[u8], max_units: u64, value: u64) -> Vec<u8> {
+        let mut args = target.as_bytes().to_vec();
+        args.extend((method.len() as u32).to_le_bytes());
+        args.extend(method.as_bytes());
+        args.extend((params.len() as u32).to_le_bytes());
+        args.extend(params);
+        args.extend(max_units.to_le_bytes());
+        args.extend(value.to_le_bytes());
+        args
+    }
+
+    /// Deploys `FORWARD` at `n + 1` addresses and returns the params for a call to `forward` on the first,
+    /// which makes `n` nested calls: to `forward` on each of the others and then to `leaf` on the last.
+    /// Each call is given 100_000 more gas than the one it makes.
+    fn forward_chain(simulator: &Simulator, n: u8) -> (Address, Vec<u8>) {
+        let addresses: Vec<_> = (0..=n).map(|i| Address::new([i; ADDRESS_LEN])).collect();
+        for address in &addresses {
+            simulator.create_contract(*address, FORWARD.as_bytes().to_vec()).unwrap();
+        }
+
+        let last = addresses[n as usize];
+        let params = addresses[1..n as usize].iter().rev().zip(2..).fold(
+            call_contract_args(last, "leaf", &[], 100_000, 0),
+            |params, (address, calls)| call_contract_args(*address, "forward", &params, calls * 100_000, 0),
+        );
+
+        (addresses[0], params)
+    }
+
+    #[test]
+    fn nested_calls_return_the_callee_result() {
+        let simulator = Simulator::new();
+        let (first, params) = forward_chain(&simulator, 2);
+
+        let result = simulator.call_contract(first, "forward", &params, 1_000_000).unwrap();
+        let chain_gas = simulator.last_gas_used();
+
+        // `Ok(Ok(42))`
+        assert_eq!(result, [[0, 0].as_slice(), &42i64.to_le_bytes()].concat());
+
+        simulator.call_contract(first, "leaf", &[], 1_000_000).unwrap();
+        assert!(chain_gas > simulator.last_gas_used());
+    }
+
+    #[test]
+    fn call_depth_is_limited() {
+        let simulator = Simulator::new();
+
+        let (first, params) = forward_chain(&simulator, MAX_CALL_DEPTH as u8);
+        let result = simulator.call_contract(first, "forward", &params, 10_000_000).unwrap();
+        assert_eq!(result[..MAX_CALL_DEPTH], [0; MAX_CALL_DEPTH]);
+        assert_eq!(result[MAX_CALL_DEPTH..], 42i64.to_le_bytes());
+
+        let (first, params) = forward_chain(&simulator, MAX_CALL_DEPTH as u8 + 1);
+        let result = simulator.call_contract(first, "forward", &params, 10_000_000).unwrap();
+        // the innermost call fails with `ExternalCallError::ExecutionFailure`
+        assert_eq!(result[..MAX_CALL_DEPTH], [0; MAX_CALL_DEPTH]);
+        assert_eq!(result[MAX_CALL_DEPTH..], [1, 0]);
+    }
+
+    #[test]
+    fn call_contract_requires_deployment() {
+        let simulator = Simulator::new();
+        let missing = Address::new([9; ADDRESS_LEN]);
+        let err = simulator.call_contract(missing, "increment", &[], 100_000).unwrap_err();
+
+        assert!(matches!(err, SimulatorError::ContractNotFound(address) if address == missing), "{err}");
+        assert_eq!(err.to_string(), format!("No contract deployed at {}", "09".repeat(ADDRESS_LEN)));
+        assert_eq!(format!("{missing:?}"), format!("Address({missing})"));
+    }
+
+    // `fail` writes `k = v`, emits `e` and traps, `spin` writes `k = v` and runs out of gas,
+    // `emit` emits `e`
+    const CALLEE: &str = r#"
+        (module
+            (import "state" "put" (func $put (param i32 i32)))
+            (import "contract" "emit_event" (func $emit_event (param i32 i32)))
+            (memory (export "memory") 1)
+            ;; borsh `vec![(b"k", b"v")]`
+            (data (i32.const 0) "\01\00\00\00\01\00\00\00k\01\00\00\00v")
+            ;; borsh `("e", vec![])`
+            (data (i32.const 16) "\01\00\00\00e\00\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "fail") (param i32)
+                (call $put (i32.const 0) (i32.const 14))
+                (call $emit_event (i32.const 16) (i32.const 9))
+                unreachable)
+            (func (export "spin") (param i32)
+                (call $put (i32.const 0) (i32.const 14))
+                (loop $forever (br $forever)))
+            (func (export "emit") (param i32)
+                (call $emit_event (i32.const 16) (i32.const 9))))
+    "#;
+
+    /// Deploys `FORWARD` and `CALLEE`, giving `FORWARD` a balance of 10.
+    fn caller_and_callee(simulator: &Simulator) -> (Address, Address) {
+        let (caller, callee) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.create_contract(caller, FORWARD.as_bytes().to_vec()).unwrap();
+        simulator.create_contract(callee, CALLEE.as_bytes().to_vec()).unwrap();
+        simulator.set_balance(caller, 10);
+        (caller, callee)
+    }
+
+    #[test]
+    fn failed_calls_are_rolled_back() {
+        // `ExternalCallError::CallPanicked` and `ExternalCallError::OutOfFuel`
+        for (method, error) in [("fail", 1), ("spin", 2)] {
+            let simulator = Simulator::new();
+            let (caller, callee) = caller_and_callee(&simulator);
+
+            let args = call_contract_args(callee, method, &[], 100_000, 5);
+            let result = simulator.call_contract(caller, "forward", &args, 1_000_000).unwrap();
+
+            assert_eq!(result, [1, error], "{method}");
+            assert_eq!(simulator.get_balance(caller), 10, "{method}");
+            assert_eq!(simulator.get_balance(callee), 0, "{method}");
+
+            let value = simulator.get_state().read().unwrap().get_contract_value(&callee, b"k").cloned();
+            assert_eq!(value, None, "{method}");
+            assert_eq!(simulator.get_events(), [], "{method}");
+        }
+    }
+
+    #[test]
+    fn callees_run_as_the_calling_contract() {
+        let simulator = Simulator::new();
+        let (caller, callee) = caller_and_callee(&simulator);
+        simulator.set_actor(Address::new([9; ADDRESS_LEN]));
+
+        let args = call_contract_args(callee, "emit", &[], 100_000, 5);
+        let result = simulator.call_contract(caller, "forward", &args, 1_000_000).unwrap();
+
+        // `Ok(())`
+        assert_eq!(result, [0]);
+        assert_eq!(simulator.get_balance(caller), 5);
+        assert_eq!(simulator.get_balance(callee), 5);
+
+        let events = simulator.get_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].contract, Some(callee));
+        assert_eq!(events[0].actor, caller);
+    }
+
+    #[test]
+    fn callees_cannot_be_given_more_gas_than_is_left() {
+        let simulator = Simulator::new();
+        let (caller, callee) = caller_and_callee(&simulator);
+
+        let args = call_contract_args(callee, "emit", &[], 2_000_000, 5);
+        let result = simulator.call_contract(caller, "forward", &args, 1_000_000).unwrap();
+
+        // `Err(ExternalCallError::OutOfFuel)`
+        assert_eq!(result, [1, 2]);
+        assert_eq!(simulator.get_balance(caller), 10);
+        assert_eq!(simulator.get_events(), []);
+    }
+
+    // a bump allocator, so that results returned by the host do not overlap
+    const BALANCE: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "balance" "get" (func $get (param i32 i32) (result i32)))
+            (import "balance" "send" (func $send (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            ;; takes an address, returns its balance
+            (func (export "balance_of") (param $ptr i32)
+                (call $set_call_result (call $get (local.get $ptr) (i32.const 33)) (i32.const 8)))
+            ;; takes `(to, amount)`, returns the borsh-encoded `Result<(), ExternalCallError>`
+            (func (export "send") (param $ptr i32)
+                (local $result i32)
+                (local.set $result (call $send (local.get $ptr) (i32.const 41)))
+                (call $set_call_result
+                    (local.get $result)
+                    (select (i32.const 1) (i32.const 2) (i32.eqz (i32.load8_u (local.get $result)))))))
+    "#;
+
+    fn send_args(to: Address, amount: u64) -> Vec<u8> {
+        [to.as_bytes(), &amount.to_le_bytes()].concat()
+    }
+
+    #[test]
+    fn send_moves_balance_from_actor() {
+        let simulator = Simulator::new();
+        let (actor, recipient) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.set_actor(actor);
+        simulator.set_balance(actor, 100);
+
+        let result = simulator
+            .execute_wasm(BALANCE.as_bytes(), "send", &send_args(recipient, 30), 100_000)
+            .unwrap();
+
+        assert_eq!(result, [0]);
+        assert_eq!(simulator.get_balance(actor), 70);
+        assert_eq!(simulator.get_balance(recipient), 30);
+    }
+
+    #[test]
+    fn send_without_funds_fails() {
+        let simulator = Simulator::new();
+        let (actor, recipient) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.set_actor(actor);
+        simulator.set_balance(actor, 10);
+
+        let result = simulator
+            .execute_wasm(BALANCE.as_bytes(), "send", &send_args(recipient, 11), 100_000)
+            .unwrap();
+
+        // `Err(ExternalCallError::InsufficientFunds)`
+        assert_eq!(result, [1, 3]);
+        assert_eq!(simulator.get_balance(actor), 10);
+        assert_eq!(simulator.get_balance(recipient), 0);
+    }
+
+    #[test]
+    fn deployed_contracts_send_their_own_balance() {
+        let simulator = Simulator::new();
+        let (actor, contract, recipient) =
+            (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]), Address::new([3; ADDRESS_LEN]));
+        simulator.set_actor(actor);
+        simulator.set_balance(actor, 100);
+        simulator.set_balance(contract, 50);
+        simulator.create_contract(contract, BALANCE.as_bytes().to_vec()).unwrap();
+
+        let result = simulator.call_contract(contract, "send", &send_args(recipient, 30), 100_000).unwrap();
+
+        assert_eq!(result, [0]);
+        assert_eq!(simulator.get_balance(actor), 100);
+        assert_eq!(simulator.get_balance(contract), 20);
+        assert_eq!(simulator.get_balance(recipient), 30);
+    }
+
+    #[test]
+    fn balance_is_read_back_after_send() {
+        let simulator = Simulator::new();
+        let (actor, recipient) = (Address::new([1; ADDRESS_LEN]), Address::new([2; ADDRESS_LEN]));
+        simulator.set_actor(actor);
+        simulator.set_balance(actor, 5);
+
+        for _ in 0..2 {
+            simulator
+                .execute_wasm(BALANCE.as_bytes(), "send", &send_args(recipient, 2), 100_000)
+                .unwrap();
+        }
+
+        for (account, expected) in [(actor, 1u64), (recipient, 4)] {
+            let balance: u64 = simulator
+                .execute_typed(BALANCE.as_bytes(), "balance_of", &account.into_bytes(), 100_000)
+                .unwrap();
+            assert_eq!(balance, expected);
+        }
+    }
 }
 
 // For wasm32 target, provide dummy types
@@ -0,0 +1,145 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Human-readable names for addresses, see [`Simulator::set_contract_name`].
+
+use crate::{Address, Simulator, SimulatorError};
+use std::collections::BTreeMap;
+
+/// Names given to addresses, which are only ever used to render them.
+pub(crate) type Names = BTreeMap<Address, String>;
+
+impl Simulator {
+    /// Names `address` so that it is rendered as `name (0x0101…)` by [`Simulator::display_address`]
+    /// and in [`Explanation`](crate::Explanation)s. Names are never used to look addresses up,
+    /// and naming an address again replaces its name.
+    /// Returns [`SimulatorError::NameTaken`] if another address already has `name`.
+    pub fn set_contract_name(
+        &self,
+        address: Address,
+        name: impl Into<String>,
+    ) -> Result<(), SimulatorError> {
+        let name = name.into();
+        let mut names = self.names.write().unwrap();
+
+        if let Some((other, _)) = names
+            .iter()
+            .find(|(other, other_name)| **other != address && **other_name == name)
+        {
+            return Err(SimulatorError::NameTaken {
+                name,
+                address: *other,
+            });
+        }
+
+        names.insert(address, name);
+        Ok(())
+    }
+
+    pub fn contract_name(&self, address: &Address) -> Option<String> {
+        self.names.read().unwrap().get(address).cloned()
+    }
+
+    /// Renders `address` as `name (0x0101…)` if it has been named, or as its full hex otherwise.
+    pub fn display_address(&self, address: &Address) -> String {
+        display(&self.names.read().unwrap(), address)
+    }
+
+    /// The names of those of `addresses` that have one.
+    pub(crate) fn names_of<'a>(&self, addresses: impl IntoIterator<Item = &'a Address>) -> Names {
+        let names = self.names.read().unwrap();
+        addresses
+            .into_iter()
+            .filter_map(|address| Some((*address, names.get(address)?.clone())))
+            .collect()
+    }
+}
+
+pub(crate) fn display(names: &Names, address: &Address) -> String {
+    match names.get(address) {
+        Some(name) => {
+            let bytes = address.as_bytes();
+            format!("{name} (0x{:02x}{:02x}…)", bytes[0], bytes[1])
+        }
+        None => address.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Address, Simulator, SimulatorError, ADDRESS_LEN};
+
+    // sends 3 of its own balance to `RECIPIENT`
+    const PAYER: &str = r#"
+        (module
+            (import "balance" "send" (func $send (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            ;; borsh `(RECIPIENT, 3u64)`
+            (data (i32.const 0) "\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\02\03\00\00\00\00\00\00\00")
+            (func (export "alloc") (param i32) (result i32) i32.const 1024)
+            (func (export "pay") (param i32)
+                (drop (call $send (i32.const 0) (i32.const 41)))))
+    "#;
+
+    const PAYER_ADDRESS: Address = Address::new([1; ADDRESS_LEN]);
+    const RECIPIENT: Address = Address::new([2; ADDRESS_LEN]);
+    const ACTOR: Address = Address::new([0xab; ADDRESS_LEN]);
+
+    #[test]
+    fn names_are_rendered() {
+        let simulator = Simulator::new();
+        simulator
+            .create_contract(PAYER_ADDRESS, PAYER.as_bytes().to_vec())
+            .unwrap();
+        simulator.set_actor(ACTOR);
+        simulator.set_balance(PAYER_ADDRESS, 10);
+        simulator.set_contract_name(PAYER_ADDRESS, "payer").unwrap();
+        simulator.set_contract_name(RECIPIENT, "token_b").unwrap();
+
+        assert_eq!(simulator.display_address(&RECIPIENT), "token_b (0x0202…)");
+        assert_eq!(simulator.display_address(&ACTOR), ACTOR.to_string());
+
+        let explanation = simulator.explain(PAYER_ADDRESS, "pay", &[], 100_000);
+        let rendered = explanation.to_string();
+
+        assert!(rendered.starts_with("payer (0x0101…).pay\n"), "{rendered}");
+        assert!(
+            rendered.contains("\n    token_b (0x0202…): 0 -> 3 (+3)"),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains("\n    payer (0x0101…): 10 -> 7 (-3)"),
+            "{rendered}"
+        );
+        assert!(
+            explanation.to_json().ends_with(&format!(
+                r#""names":{{"{PAYER_ADDRESS}":"payer","{RECIPIENT}":"token_b"}}}}"#
+            )),
+            "{}",
+            explanation.to_json()
+        );
+    }
+
+    #[test]
+    fn names_are_unique() {
+        let simulator = Simulator::new();
+        simulator.set_contract_name(PAYER_ADDRESS, "token").unwrap();
+
+        let err = simulator.set_contract_name(RECIPIENT, "token").unwrap_err();
+        assert!(
+            matches!(&err, SimulatorError::NameTaken { name, address } if name == "token" && *address == PAYER_ADDRESS),
+            "{err}"
+        );
+
+        // renaming frees the old name
+        simulator
+            .set_contract_name(PAYER_ADDRESS, "token_a")
+            .unwrap();
+        simulator.set_contract_name(RECIPIENT, "token").unwrap();
+        assert_eq!(
+            simulator.contract_name(&RECIPIENT).as_deref(),
+            Some("token")
+        );
+        assert_eq!(simulator.contract_name(&ACTOR), None);
+    }
+}
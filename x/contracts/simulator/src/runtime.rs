@@ -0,0 +1,764 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Runs contract methods with wasmtime.
+//!
+//! Contracts follow the wasmlanche calling convention: every public method takes a
+//! single pointer to its serialized arguments, allocated through the exported `alloc`
+//! function, and returns its result through the `contract.set_call_result` import.
+//!
+//! State is read and written through the `state` imports:
+//! * `get(args_ptr, args_len) -> value_ptr` takes a borsh-encoded `{ key: Vec<u8> }` and returns
+//!   a pointer to a copy of the value allocated with `alloc`, or `0` if the key is absent
+//! * `put(args_ptr, args_len)` takes a borsh-encoded `Vec<(key, value)>`, an empty value deletes the key.
+//!   It charges [`STATE_WRITE_COST`] however many pairs it writes
+//! * `delete(args_ptr, args_len) -> value_ptr` takes the same borsh-encoded `{ key: Vec<u8> }` as `get`,
+//!   removes the key and returns its previous value like `get`
+//! * `scan(args_ptr, args_len) -> page_ptr` takes a borsh-encoded `(prefix: Vec<u8>, offset: u32, limit: u32)`
+//!   and returns a pointer to `(count: u32, entries: Vec<(key, value)>)` allocated with `alloc`, where `count`
+//!   is the number of keys starting with `prefix` and `entries` holds at most `limit` of them in ascending order,
+//!   skipping the first `offset`
+//! * `fold_prefix(prefix_ptr, prefix_len, op, arg_ptr, arg_len) -> result_ptr` runs one of a fixed set of
+//!   aggregates over every key starting with the raw `prefix`, taking a borsh-encoded argument, and returns a
+//!   pointer to its borsh-encoded result allocated with `alloc`. It charges [`FOLD_GAS_PER_ENTRY`] for every
+//!   entry it visits, see [`fold`] for the operations
+//!
+//! Keys are namespaced by the address of the contract being called, when there is one,
+//! so contracts cannot see each other's state.
+//!
+//! Balances are read and moved through the `balance` imports, which return pointers to borsh-encoded
+//! results allocated with `alloc`:
+//! * `get(args_ptr, args_len) -> balance_ptr` takes an address and returns its `u64` balance
+//! * `send(args_ptr, args_len) -> result_ptr` takes `(to, amount)` and moves `amount` from the contract being
+//!   called to `to`, or from the actor when there is no contract, returning `Result<(), ExternalCallError>`
+//!   with `InsufficientFunds` if the sender cannot cover it
+//!
+//! Events are emitted through `contract.emit_event(args_ptr, args_len)`, which takes a borsh-encoded
+//! `(name: String, data: Vec<u8>)`, and recorded with the actor and the current block.
+//! A call may emit at most [`MAX_EVENTS_PER_CALL`] events of at most [`MAX_EVENT_DATA_SIZE`] bytes of data each.
+//!
+//! Bech32 strings are converted through the `addr` imports, which charge [`BECH32_GAS_PER_BYTE`]
+//! for every byte of their arguments and return pointers to borsh-encoded `Result<_, Bech32Error>`s
+//! allocated with `alloc`:
+//! * `bech32_encode(args_ptr, args_len) -> result_ptr` takes `(hrp: String, data: Vec<u8>)` and returns the string
+//! * `bech32_decode(args_ptr, args_len) -> result_ptr` takes `(hrp: String, encoded: String)` and returns the data,
+//!   failing with `WrongHrp` if the string has a different human-readable part
+//!
+//! The `addr` imports, `state.fold_prefix` and `contract.emit_event` only exist in the simulator,
+//! contracts reach them through wasmlanche's `simulator-imports` feature.
+//!
+//! The current block is read through `env.height() -> i64` and `env.timestamp() -> i64`.
+//!
+//! `contract.remaining_fuel() -> fuel_ptr` returns a pointer to the borsh-encoded `u64` of gas the call has left,
+//! allocated with `alloc`.
+//!
+//! `contract.set_call_result` charges [`SET_RESULT_COST`] whatever the length of the result.
+//!
+//! Other deployed contracts are called through `contract.call_contract(args_ptr, args_len) -> result_ptr`,
+//! which takes wasmlanche's borsh-encoded `CallContractArgs` and returns the callee's result as
+//! `Result<_, ExternalCallError>`. The callee runs as the calling contract with the `max_units` of gas
+//! it was given, failing with `OutOfFuel` if the caller has less than that left, and whatever it uses
+//! is deducted from the caller. A failed callee's state changes, events and value are rolled back.
+//! Calls nest at most [`MAX_CALL_DEPTH`] deep.
+//!
+//! With the `tracing` feature, calls and host imports are recorded as described in [`trace`].
+//!
+//! Each contract's linear memory may grow to at most the call's memory limit, growing past it traps.
+//! Calls with a deadline are interrupted once the engine's epoch, advanced every [`EPOCH_TICK`]
+//! while a timeout is set, passes it.
+
+use crate::{
+    addr, backtrace, fold, trace, Address, Event, SimulatorError, SimulatorState, ADDRESS_LEN,
+    BECH32_GAS_PER_BYTE, FOLD_GAS_PER_ENTRY, MAX_EVENTS_PER_CALL, MAX_EVENT_DATA_SIZE,
+};
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use wasmtime::{
+    Caller, Config, Engine, Extern, ExternType, Linker, Memory, Module, ResourceLimiter, Store,
+    Trap,
+};
+
+const ALLOC_FN_NAME: &str = "alloc";
+const MEMORY_NAME: &str = "memory";
+
+/// How many contract calls can be nested below the call made by the simulator.
+pub const MAX_CALL_DEPTH: usize = 16;
+
+/// How often the engine's epoch is advanced while a timeout is set.
+pub(crate) const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// The default for [`Simulator::set_memory_limit`](crate::Simulator::set_memory_limit), 64 MiB.
+pub const DEFAULT_MEMORY_LIMIT: usize = 64 << 20;
+
+/// Fuel charged by `state.put`, the runtime's flat `putCost`.
+pub const STATE_WRITE_COST: u64 = 10_000;
+
+/// Fuel charged by `contract.set_call_result`, the runtime's flat `setCallResultCost`.
+pub const SET_RESULT_COST: u64 = 10_000;
+
+// discriminants of wasmlanche's `ExternalCallError`
+const EXECUTION_FAILURE: u8 = 0;
+const CALL_PANICKED: u8 = 1;
+const OUT_OF_FUEL: u8 = 2;
+const INSUFFICIENT_FUNDS: u8 = 3;
+
+/// Borsh encoding of `Ok::<(), ExternalCallError>(())`.
+const SEND_OK: &[u8] = &[0];
+/// Borsh encoding of `Err::<(), _>(ExternalCallError::InsufficientFunds)`.
+const SEND_INSUFFICIENT_FUNDS: &[u8] = &[1, INSUFFICIENT_FUNDS];
+
+/// Data owned by the [`Store`] for the duration of a single call.
+pub(crate) struct CallData {
+    result: Option<Vec<u8>>,
+    state: Arc<RwLock<SimulatorState>>,
+    /// The account that made the call.
+    actor: Address,
+    /// The contract whose state the call uses, or `None` to use the unnamespaced state.
+    contract: Option<Address>,
+    /// How many `call_contract` calls this call is nested in.
+    depth: usize,
+    /// The most bytes of linear memory the contract may use.
+    memory_limit: usize,
+    /// When the call, including any calls it makes, is interrupted.
+    deadline: Option<Instant>,
+    /// How many events the call has emitted.
+    events_emitted: usize,
+}
+
+impl CallData {
+    pub(crate) fn new(
+        state: Arc<RwLock<SimulatorState>>,
+        actor: Address,
+        contract: Option<Address>,
+        memory_limit: usize,
+        deadline: Option<Instant>,
+    ) -> Self {
+        Self {
+            result: None,
+            state,
+            actor,
+            contract,
+            depth: 0,
+            memory_limit,
+            deadline,
+            events_emitted: 0,
+        }
+    }
+
+    /// How many epoch ticks the call has left before its deadline.
+    fn epoch_deadline(&self) -> u64 {
+        self.deadline.map_or(u64::MAX, |deadline| {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let ticks = remaining.as_nanos().div_ceil(EPOCH_TICK.as_nanos());
+            u64::try_from(ticks).unwrap_or(u64::MAX)
+        })
+    }
+
+    /// The account that balances are sent from: the contract being called, or the actor
+    /// for calls that are not to a deployed contract.
+    fn sender(&self) -> Address {
+        self.contract.unwrap_or(self.actor)
+    }
+
+    fn state_key(&self, key: &[u8]) -> Vec<u8> {
+        match &self.contract {
+            Some(contract) => SimulatorState::contract_key(contract, key),
+            None => key.to_vec(),
+        }
+    }
+}
+
+/// The outcome of [`call`] along with the gas it consumed, which is reported even on failure.
+pub(crate) struct CallOutcome {
+    pub result: Result<Vec<u8>, SimulatorError>,
+    pub gas_used: u64,
+}
+
+/// Builds an engine that meters execution with fuel, one unit of fuel per unit of gas.
+/// With the `debug-info` feature, trap backtraces also show the source location of each frame.
+pub(crate) fn engine() -> Engine {
+    let mut config = Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+    #[cfg(feature = "debug-info")]
+    config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Enable);
+    Engine::new(&config).expect("fuel metering and epoch interruption are always supported")
+}
+
+/// Instantiates `code` and calls `method` with `params`, stopping once `gas` is exhausted.
+pub(crate) fn call(
+    engine: &Engine,
+    call_data: CallData,
+    code: &[u8],
+    method: &str,
+    params: &[u8],
+    gas: u64,
+) -> CallOutcome {
+    let span = trace::span!(
+        "call",
+        contract = call_data.contract.map(|contract| contract.to_string()),
+        method,
+        gas,
+        depth = call_data.depth,
+        gas_used = tracing::field::Empty,
+    )
+    .entered();
+
+    let mut store = Store::new(engine, call_data);
+    store.limiter(|call_data| call_data as &mut dyn ResourceLimiter);
+    store.set_epoch_deadline(store.data().epoch_deadline());
+
+    let result = store
+        .set_fuel(gas)
+        .map_err(execution_error)
+        .and_then(|()| call_in_store(&mut store, code, method, params));
+
+    let gas_used = gas - store.get_fuel().unwrap_or(0);
+    span.record("gas_used", gas_used);
+
+    CallOutcome { result, gas_used }
+}
+
+fn call_in_store(
+    store: &mut Store<CallData>,
+    code: &[u8],
+    method: &str,
+    params: &[u8],
+) -> Result<Vec<u8>, SimulatorError> {
+    let module = trace::span!("compile", bytes = code.len())
+        .in_scope(|| Module::new(store.engine(), code))
+        .map_err(execution_error)?;
+
+    let exports = module
+        .exports()
+        .filter(|export| matches!(export.ty(), ExternType::Func(_)))
+        .map(|export| export.name())
+        .filter(|name| *name != ALLOC_FN_NAME);
+
+    if module.get_export(method).is_none() {
+        return Err(SimulatorError::method_not_found(method, exports));
+    }
+
+    let instance = trace::span!("instantiate").in_scope(|| {
+        linker(store.engine(), &module)?
+            .instantiate(&mut *store, &module)
+            .map_err(trap_error)
+    })?;
+
+    let _execute = trace::span!("execute", params = params.len()).entered();
+
+    let params_ptr = if params.is_empty() {
+        0
+    } else {
+        let alloc = instance
+            .get_typed_func::<u32, u32>(&mut *store, ALLOC_FN_NAME)
+            .map_err(execution_error)?;
+        let memory = instance
+            .get_memory(&mut *store, MEMORY_NAME)
+            .ok_or_else(|| {
+                SimulatorError::ContractExecution(format!("missing `{MEMORY_NAME}` export"))
+            })?;
+
+        let len = u32::try_from(params.len()).map_err(execution_error)?;
+        let ptr = alloc.call(&mut *store, len).map_err(trap_error)?;
+        memory
+            .write(&mut *store, ptr as usize, params)
+            .map_err(execution_error)?;
+        ptr
+    };
+
+    instance
+        .get_typed_func::<u32, ()>(&mut *store, method)
+        .map_err(execution_error)?
+        .call(&mut *store, params_ptr)
+        .map_err(trap_error)?;
+
+    Ok(store.data_mut().result.take().unwrap_or_default())
+}
+
+fn linker(engine: &Engine, module: &Module) -> Result<Linker<CallData>, SimulatorError> {
+    let mut linker = Linker::new(engine);
+
+    linker
+        .func_wrap("contract", "set_call_result", set_call_result)
+        .and_then(|linker| linker.func_wrap("contract", "call_contract", call_contract))
+        .and_then(|linker| linker.func_wrap("contract", "remaining_fuel", remaining_fuel))
+        .and_then(|linker| linker.func_wrap("contract", "emit_event", emit_event))
+        .and_then(|linker| linker.func_wrap("state", "get", state_get))
+        .and_then(|linker| linker.func_wrap("state", "put", state_put))
+        .and_then(|linker| linker.func_wrap("state", "delete", state_delete))
+        .and_then(|linker| linker.func_wrap("state", "scan", state_scan))
+        .and_then(|linker| linker.func_wrap("state", "fold_prefix", state_fold_prefix))
+        .and_then(|linker| linker.func_wrap("balance", "get", balance_get))
+        .and_then(|linker| linker.func_wrap("balance", "send", balance_send))
+        .and_then(|linker| linker.func_wrap("addr", "bech32_encode", addr_bech32_encode))
+        .and_then(|linker| linker.func_wrap("addr", "bech32_decode", addr_bech32_decode))
+        .and_then(|linker| linker.func_wrap("env", "height", env_height))
+        .and_then(|linker| linker.func_wrap("env", "timestamp", env_timestamp))
+        .map_err(execution_error)?;
+
+    // host functions the simulator does not provide yet trap when called,
+    // rather than preventing the contract from being instantiated at all
+    linker
+        .define_unknown_imports_as_traps(module)
+        .map_err(execution_error)?;
+
+    Ok(linker)
+}
+
+fn set_call_result(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<()> {
+    charge(&mut caller, SET_RESULT_COST)?;
+    let result = read_guest(&mut caller, ptr, len)?;
+    trace::import!("contract.set_call_result", bytes_in = len);
+    caller.data_mut().result = Some(result);
+
+    Ok(())
+}
+
+fn remaining_fuel(mut caller: Caller<'_, CallData>) -> wasmtime::Result<u32> {
+    let fuel = caller.get_fuel()?;
+    trace::import!("contract.remaining_fuel", bytes_out = 8);
+    write_guest(&mut caller, &fuel.to_le_bytes())
+}
+
+fn call_contract(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<u32> {
+    // `CallContractArgs { address, function_name, args, max_units, value }`
+    let (target, method, params, max_units, value): ([u8; ADDRESS_LEN], String, Vec<u8>, u64, u64) =
+        borsh::from_slice(&read_guest(&mut caller, ptr, len)?)?;
+
+    let result = match nested_call(
+        &mut caller,
+        Address::new(target),
+        &method,
+        &params,
+        max_units,
+        value,
+    )? {
+        Ok(result) => [&[0], result.as_slice()].concat(),
+        Err(error) => vec![1, error],
+    };
+
+    trace::import!(
+        "contract.call_contract",
+        bytes_in = len,
+        bytes_out = result.len()
+    );
+    write_guest(&mut caller, &result)
+}
+
+/// Calls `method` on `target` on behalf of [`call_contract`],
+/// returning the `ExternalCallError` discriminant if the call fails.
+///
+/// The callee's actor is the calling contract, and `value` is sent to it from the caller's balance.
+/// A callee that fails leaves no trace: its state changes, its events and `value` are rolled back.
+fn nested_call(
+    caller: &mut Caller<'_, CallData>,
+    target: Address,
+    method: &str,
+    params: &[u8],
+    max_units: u64,
+    value: u64,
+) -> wasmtime::Result<Result<Vec<u8>, u8>> {
+    let remaining = caller.get_fuel()?;
+    if max_units > remaining {
+        return Ok(Err(OUT_OF_FUEL));
+    }
+
+    let data = caller.data();
+
+    if data.depth >= MAX_CALL_DEPTH {
+        return Ok(Err(EXECUTION_FAILURE));
+    }
+
+    let Some(code) = data.state.read().unwrap().get_contract(&target).cloned() else {
+        return Ok(Err(EXECUTION_FAILURE));
+    };
+
+    let snapshot = data.state.read().unwrap().snapshot();
+    let from = data.sender();
+    if !transfer(&mut data.state.write().unwrap(), from, target, value)? {
+        return Ok(Err(INSUFFICIENT_FUNDS));
+    }
+
+    let call_data = CallData {
+        depth: data.depth + 1,
+        ..CallData::new(
+            data.state.clone(),
+            from,
+            Some(target),
+            data.memory_limit,
+            data.deadline,
+        )
+    };
+
+    let engine = caller.engine().clone();
+    let outcome = call(&engine, call_data, &code, method, params, max_units);
+    caller.set_fuel(remaining - outcome.gas_used)?;
+
+    if outcome.result.is_err() {
+        caller.data().state.write().unwrap().restore(snapshot);
+    }
+
+    Ok(outcome.result.map_err(|err| match err {
+        SimulatorError::OutOfGas => OUT_OF_FUEL,
+        SimulatorError::ContractExecution(_) => CALL_PANICKED,
+        _ => EXECUTION_FAILURE,
+    }))
+}
+
+/// Trap raised when a contract's memory would grow past its limit.
+#[derive(Debug)]
+struct MemoryLimitExceeded;
+
+impl std::fmt::Display for MemoryLimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "memory limit exceeded")
+    }
+}
+
+impl std::error::Error for MemoryLimitExceeded {}
+
+impl ResourceLimiter for CallData {
+    fn memory_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        if desired > self.memory_limit {
+            return Err(MemoryLimitExceeded.into());
+        }
+
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        _desired: usize,
+        _maximum: Option<usize>,
+    ) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+fn emit_event(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<()> {
+    let (name, data): (String, Vec<u8>) = borsh::from_slice(&read_guest(&mut caller, ptr, len)?)?;
+    trace::import!("contract.emit_event", bytes_in = len);
+
+    let call_data = caller.data_mut();
+    if call_data.events_emitted >= MAX_EVENTS_PER_CALL {
+        return Err(wasmtime::Error::msg(format!(
+            "a call may emit at most {MAX_EVENTS_PER_CALL} events"
+        )));
+    }
+    if data.len() > MAX_EVENT_DATA_SIZE {
+        return Err(wasmtime::Error::msg(format!(
+            "event `{name}` has {} bytes of data, the most allowed is {MAX_EVENT_DATA_SIZE}",
+            data.len()
+        )));
+    }
+    call_data.events_emitted += 1;
+
+    let mut state = call_data.state.write().unwrap();
+    let event = Event {
+        contract: call_data.contract,
+        actor: call_data.actor,
+        height: state.get_height(),
+        timestamp: state.get_timestamp(),
+        name,
+        data,
+        sequence: state.events().len() as u64,
+    };
+    state.push_event(event);
+
+    Ok(())
+}
+
+fn state_get(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<u32> {
+    // a borsh-encoded `GetArgs { key }` is laid out exactly like the key on its own
+    let key: Vec<u8> = borsh::from_slice(&read_guest(&mut caller, ptr, len)?)?;
+    let key = caller.data().state_key(&key);
+    let value = caller.data().state.read().unwrap().get_value(&key).cloned();
+    trace::import!(
+        "state.get",
+        bytes_in = len,
+        bytes_out = value.as_ref().map_or(0, Vec::len)
+    );
+
+    match value {
+        Some(value) => write_guest(&mut caller, &value),
+        None => Ok(0),
+    }
+}
+
+fn state_put(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<()> {
+    charge(&mut caller, STATE_WRITE_COST)?;
+    let pairs: Vec<(Vec<u8>, Vec<u8>)> = borsh::from_slice(&read_guest(&mut caller, ptr, len)?)?;
+    trace::import!("state.put", bytes_in = len, pairs = pairs.len());
+    let call_data = caller.data();
+    let mut state = call_data.state.write().unwrap();
+
+    for (key, value) in pairs {
+        let key = call_data.state_key(&key);
+        if value.is_empty() {
+            state.delete_value(&key);
+        } else {
+            state.set_value(key, value);
+        }
+    }
+
+    Ok(())
+}
+
+fn state_delete(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<u32> {
+    // takes the same borsh-encoded `{ key }` as `state.get`
+    let key: Vec<u8> = borsh::from_slice(&read_guest(&mut caller, ptr, len)?)?;
+    let key = caller.data().state_key(&key);
+    let previous = caller.data().state.write().unwrap().delete_value(&key);
+    trace::import!(
+        "state.delete",
+        bytes_in = len,
+        bytes_out = previous.as_ref().map_or(0, Vec::len),
+    );
+
+    match previous {
+        Some(previous) => write_guest(&mut caller, &previous),
+        None => Ok(0),
+    }
+}
+
+fn state_scan(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<u32> {
+    let (prefix, offset, limit): (Vec<u8>, u32, u32) =
+        borsh::from_slice(&read_guest(&mut caller, ptr, len)?)?;
+    let call_data = caller.data();
+    let namespace = call_data.state_key(&[]).len();
+    let prefix = call_data.state_key(&prefix);
+
+    let page = {
+        let state = call_data.state.read().unwrap();
+        let count = u32::try_from(state.get_by_prefix(&prefix).count())?;
+        let entries: Vec<_> = state
+            .get_by_prefix(&prefix)
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(key, value)| (&key[namespace..], value))
+            .collect();
+        borsh::to_vec(&(count, entries))?
+    };
+    trace::import!("state.scan", bytes_in = len, bytes_out = page.len());
+
+    write_guest(&mut caller, &page)
+}
+
+fn state_fold_prefix(
+    mut caller: Caller<'_, CallData>,
+    prefix_ptr: u32,
+    prefix_len: u32,
+    op: u32,
+    arg_ptr: u32,
+    arg_len: u32,
+) -> wasmtime::Result<u32> {
+    let prefix = read_guest(&mut caller, prefix_ptr, prefix_len)?;
+    let arg = read_guest(&mut caller, arg_ptr, arg_len)?;
+    let affordable = caller.get_fuel()? / FOLD_GAS_PER_ENTRY;
+    let call_data = caller.data();
+    let namespace = call_data.state_key(&[]).len();
+    let prefix = call_data.state_key(&prefix);
+
+    let folded = {
+        let state = call_data.state.read().unwrap();
+        let entries = state
+            .get_by_prefix(&prefix)
+            .map(|(key, value)| (&key[namespace..], value));
+        fold::fold(op, &arg, entries, affordable)?
+    };
+
+    charge(
+        &mut caller,
+        FOLD_GAS_PER_ENTRY.saturating_mul(folded.visited),
+    )?;
+    trace::import!(
+        "state.fold_prefix",
+        bytes_in = prefix_len + arg_len,
+        bytes_out = folded.result.len(),
+        visited = folded.visited,
+    );
+    write_guest(&mut caller, &folded.result)
+}
+
+fn balance_get(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<u32> {
+    let account = read_address(&read_guest(&mut caller, ptr, len)?)?;
+    let balance = caller.data().state.read().unwrap().get_balance(&account);
+    trace::import!("balance.get", bytes_in = len, bytes_out = 8);
+
+    write_guest(&mut caller, &balance.to_le_bytes())
+}
+
+fn balance_send(mut caller: Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<u32> {
+    let args = read_guest(&mut caller, ptr, len)?;
+    if args.len() != ADDRESS_LEN + 8 {
+        return Err(wasmtime::Error::msg(
+            "expected borsh-encoded `(Address, u64)`",
+        ));
+    }
+
+    let (to, amount) = args.split_at(ADDRESS_LEN);
+    let to = read_address(to)?;
+    let amount = u64::from_le_bytes(amount.try_into()?);
+
+    let from = caller.data().sender();
+    let sent = transfer(&mut caller.data().state.write().unwrap(), from, to, amount)?;
+    let result = if sent {
+        SEND_OK
+    } else {
+        SEND_INSUFFICIENT_FUNDS
+    };
+    trace::import!("balance.send", bytes_in = len, bytes_out = result.len());
+
+    write_guest(&mut caller, result)
+}
+
+/// Moves `amount` from `from` to `to`, returning `false` without moving anything if `from` cannot cover it.
+fn transfer(
+    state: &mut SimulatorState,
+    from: Address,
+    to: Address,
+    amount: u64,
+) -> wasmtime::Result<bool> {
+    let Some(remaining) = state.get_balance(&from).checked_sub(amount) else {
+        return Ok(false);
+    };
+
+    if from != to {
+        let to_balance = state
+            .get_balance(&to)
+            .checked_add(amount)
+            .ok_or_else(|| wasmtime::Error::msg("recipient balance overflow"))?;
+        state.set_balance(from, remaining);
+        state.set_balance(to, to_balance);
+    }
+
+    Ok(true)
+}
+
+fn read_address(bytes: &[u8]) -> wasmtime::Result<Address> {
+    Address::try_from(bytes).map_err(wasmtime::Error::msg)
+}
+
+fn addr_bech32_encode(
+    mut caller: Caller<'_, CallData>,
+    ptr: u32,
+    len: u32,
+) -> wasmtime::Result<u32> {
+    let args = read_guest(&mut caller, ptr, len)?;
+    charge(
+        &mut caller,
+        BECH32_GAS_PER_BYTE.saturating_mul(args.len() as u64),
+    )?;
+
+    let (hrp, data): (String, Vec<u8>) = borsh::from_slice(&args)?;
+    let result = borsh::to_vec(&addr::encode(&hrp, &data))?;
+    trace::import!(
+        "addr.bech32_encode",
+        bytes_in = len,
+        bytes_out = result.len()
+    );
+    write_guest(&mut caller, &result)
+}
+
+fn addr_bech32_decode(
+    mut caller: Caller<'_, CallData>,
+    ptr: u32,
+    len: u32,
+) -> wasmtime::Result<u32> {
+    let args = read_guest(&mut caller, ptr, len)?;
+    charge(
+        &mut caller,
+        BECH32_GAS_PER_BYTE.saturating_mul(args.len() as u64),
+    )?;
+
+    let (hrp, encoded): (String, String) = borsh::from_slice(&args)?;
+    let result = borsh::to_vec(&addr::decode(&hrp, &encoded))?;
+    trace::import!(
+        "addr.bech32_decode",
+        bytes_in = len,
+        bytes_out = result.len()
+    );
+    write_guest(&mut caller, &result)
+}
+
+/// Consumes `units` of the caller's fuel, trapping like wasm code would if there isn't enough.
+fn charge(caller: &mut Caller<'_, CallData>, units: u64) -> wasmtime::Result<()> {
+    let remaining = caller.get_fuel()?;
+    caller.set_fuel(remaining.saturating_sub(units))?;
+
+    if remaining < units {
+        return Err(Trap::OutOfFuel.into());
+    }
+
+    Ok(())
+}
+
+// wasm has no unsigned integers, the guest reinterprets the bits as a `u64`
+#[allow(clippy::cast_possible_wrap)]
+fn env_height(caller: Caller<'_, CallData>) -> i64 {
+    caller.data().state.read().unwrap().get_height() as i64
+}
+
+#[allow(clippy::cast_possible_wrap)]
+fn env_timestamp(caller: Caller<'_, CallData>) -> i64 {
+    caller.data().state.read().unwrap().get_timestamp() as i64
+}
+
+fn memory(caller: &mut Caller<'_, CallData>) -> wasmtime::Result<Memory> {
+    match caller.get_export(MEMORY_NAME) {
+        Some(Extern::Memory(memory)) => Ok(memory),
+        _ => Err(wasmtime::Error::msg(format!(
+            "export `{MEMORY_NAME}` should be a memory"
+        ))),
+    }
+}
+
+/// Copies `len` bytes at `ptr` out of the guest's memory.
+fn read_guest(caller: &mut Caller<'_, CallData>, ptr: u32, len: u32) -> wasmtime::Result<Vec<u8>> {
+    let (ptr, len) = (ptr as usize, len as usize);
+
+    memory(caller)?
+        .data(&*caller)
+        .get(ptr..ptr + len)
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| wasmtime::Error::msg("guest pointer out of bounds"))
+}
+
+/// Copies `bytes` into a fresh guest allocation, returning its pointer.
+fn write_guest(caller: &mut Caller<'_, CallData>, bytes: &[u8]) -> wasmtime::Result<u32> {
+    let Some(Extern::Func(alloc)) = caller.get_export(ALLOC_FN_NAME) else {
+        return Err(wasmtime::Error::msg(format!(
+            "export `{ALLOC_FN_NAME}` should be a function"
+        )));
+    };
+
+    let alloc = alloc.typed::<u32, u32>(&*caller)?;
+    let ptr = alloc.call(&mut *caller, u32::try_from(bytes.len())?)?;
+    memory(caller)?.write(&mut *caller, ptr as usize, bytes)?;
+
+    Ok(ptr)
+}
+
+fn trap_error(err: wasmtime::Error) -> SimulatorError {
+    if err.is::<MemoryLimitExceeded>() {
+        return SimulatorError::MemoryLimitExceeded;
+    }
+
+    match err.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => SimulatorError::OutOfGas,
+        Some(Trap::Interrupt) => SimulatorError::Timeout,
+        _ => SimulatorError::ContractExecution(backtrace::render(&err)),
+    }
+}
+
+fn execution_error(err: impl std::fmt::Display) -> SimulatorError {
+    SimulatorError::ContractExecution(err.to_string())
+}
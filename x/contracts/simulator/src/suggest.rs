@@ -0,0 +1,124 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Near-miss suggestions for unknown method names.
+
+/// Prefix the toolchain adds to internal exports; never shown to users.
+const INTERNAL_PREFIX: &str = "__wasm_";
+
+/// Largest edit distance still offered as a `did you mean`.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Number of exports listed alongside a missing method.
+const MAX_LISTED: usize = 5;
+
+/// Levenshtein distance between `a` and `b`, counted in chars.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            curr[j + 1] = substitution.min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Ranks `exports` by distance to `method`, returning the best candidate if it is
+/// within [`MAX_SUGGESTION_DISTANCE`] and the [`MAX_LISTED`] closest names.
+pub fn suggest<'a, I>(method: &str, exports: I) -> (Option<String>, Vec<String>)
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut ranked: Vec<(usize, &str)> = exports
+        .into_iter()
+        .map(|name| name.strip_prefix(INTERNAL_PREFIX).unwrap_or(name))
+        .map(|name| (edit_distance(method, name), name))
+        .collect();
+
+    ranked.sort_unstable();
+    ranked.dedup_by(|a, b| a.1 == b.1);
+
+    let did_you_mean = ranked
+        .first()
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .map(|(_, name)| (*name).to_string());
+
+    let available = ranked
+        .into_iter()
+        .take(MAX_LISTED)
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+    (did_you_mean, available)
+}
+
+/// Renders the tail of a `MethodNotFound` message.
+pub(crate) fn format_hint(did_you_mean: &Option<String>, available: &[String]) -> String {
+    let mut hint = String::new();
+    if let Some(name) = did_you_mean {
+        hint.push_str(&format!("; did you mean '{name}'?"));
+    }
+    if !available.is_empty() {
+        hint.push_str(&format!(" (available: {})", available.join(", ")));
+    }
+    hint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("transfer", "transfer"), 0);
+        assert_eq!(edit_distance("transferr", "transfer"), 1);
+        assert_eq!(edit_distance("tarnsfer", "transfer"), 2);
+        assert_eq!(edit_distance("mint", "transfer"), 7);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggests_near_miss() {
+        let exports = ["balance_of", "transfer", "transfer_from", "mint"];
+        let (did_you_mean, available) = suggest("transferr", exports);
+
+        assert_eq!(did_you_mean.as_deref(), Some("transfer"));
+        assert_eq!(available[0], "transfer");
+    }
+
+    #[test]
+    fn no_suggestion_beyond_threshold() {
+        let (did_you_mean, available) = suggest("withdraw", ["transfer", "mint"]);
+
+        assert_eq!(did_you_mean, None);
+        assert_eq!(available.len(), 2);
+    }
+
+    #[test]
+    fn lists_closest_five_without_internal_prefix() {
+        let exports = [
+            "__wasm_call_ctors",
+            "a",
+            "ab",
+            "abc",
+            "abcd",
+            "abcde",
+            "abcdef",
+            "zzzzzzzzzz",
+        ];
+        let (_, available) = suggest("abcd", exports);
+
+        assert_eq!(available, ["abcd", "abc", "abcde", "ab", "abcdef"]);
+
+        let (did_you_mean, _) = suggest("call_ctor", exports);
+        assert_eq!(did_you_mean.as_deref(), Some("call_ctors"));
+    }
+}
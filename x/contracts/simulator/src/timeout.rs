@@ -0,0 +1,117 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Wall-clock limits on contract calls, see [`Simulator::set_timeout`].
+
+use crate::{runtime::EPOCH_TICK, Simulator};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+impl Simulator {
+    pub fn timeout(&self) -> Option<Duration> {
+        *self.timeout.read().unwrap()
+    }
+
+    /// Interrupts every following call that runs for longer than `timeout`, including the calls it makes,
+    /// with [`SimulatorError::Timeout`](crate::SimulatorError::Timeout).
+    /// `None`, the default, lets calls run until they run out of gas.
+    ///
+    /// Timeouts are measured in steps of 10ms, so calls may run slightly longer.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        if timeout.is_some() {
+            self.ticker
+                .get_or_init(|| Ticker::spawn(self.engine.clone()));
+        }
+
+        *self.timeout.write().unwrap() = timeout;
+    }
+}
+
+/// A thread advancing the engine's epoch every [`EPOCH_TICK`] until dropped.
+pub(crate) struct Ticker {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Ticker {
+    fn spawn(engine: wasmtime::Engine) -> Self {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stop = stopped.clone();
+        thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                thread::sleep(EPOCH_TICK);
+                engine.increment_epoch();
+            }
+        });
+
+        Self { stopped }
+    }
+}
+
+impl Drop for Ticker {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SimulatorError;
+    use std::time::Instant;
+
+    const SPIN: &str = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "spin") (param i32)
+                (loop $forever (br $forever)))
+            (func (export "noop") (param i32)))
+    "#;
+
+    #[test]
+    fn long_call_times_out() {
+        let simulator = Simulator::new();
+        simulator.set_timeout(Some(Duration::from_millis(100)));
+
+        let start = Instant::now();
+        let err = simulator
+            .execute_wasm(SPIN.as_bytes(), "spin", &[], u64::MAX)
+            .unwrap_err();
+        let elapsed = start.elapsed();
+
+        assert!(matches!(err, SimulatorError::Timeout), "{err}");
+        assert!(elapsed >= Duration::from_millis(100), "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(2), "{elapsed:?}");
+    }
+
+    #[test]
+    fn timeout_resets_between_calls() {
+        let simulator = Simulator::new();
+        simulator.set_timeout(Some(Duration::from_millis(50)));
+
+        let err = simulator
+            .execute_wasm(SPIN.as_bytes(), "spin", &[], u64::MAX)
+            .unwrap_err();
+        assert!(matches!(err, SimulatorError::Timeout), "{err}");
+
+        simulator
+            .execute_wasm(SPIN.as_bytes(), "noop", &[], 10_000)
+            .unwrap();
+    }
+
+    #[test]
+    fn no_timeout_by_default() {
+        let simulator = Simulator::new();
+        assert_eq!(simulator.timeout(), None);
+
+        let err = simulator
+            .execute_wasm(SPIN.as_bytes(), "spin", &[], 1_000_000)
+            .unwrap_err();
+        assert!(matches!(err, SimulatorError::OutOfGas), "{err}");
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Optional `tracing` instrumentation of contract calls.
+//!
+//! With the `tracing` feature, every call is recorded as a `debug` level `call` span with the
+//! contract's address, the method, the gas it was given, how deeply it is nested and, once it
+//! returns, the gas it used. Below it are `compile`, `instantiate` and `execute` spans, and calls
+//! a contract makes to other contracts are recorded below its `execute` span.
+//!
+//! Host imports are recorded as `trace` level events with the import's name and how many bytes it
+//! read from and wrote to the contract's memory. Keys, values and other contents are never recorded.
+//!
+//! Without the feature, [`span!`] and [`import!`] expand to nothing that records or allocates.
+
+/// Creates a `debug` level span, or a [`NoSpan`] without the `tracing` feature.
+macro_rules! span {
+    ($name:literal $(, $($field:tt)*)?) => {{
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!($name $(, $($field)*)?);
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::trace::NoSpan;
+        span
+    }};
+}
+
+/// Records a `trace` level event for a call to the host import named `$import`.
+macro_rules! import {
+    ($import:literal $(, $($field:tt)*)?) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!(import = $import $(, $($field)*)?);
+    };
+}
+
+pub(crate) use {import, span};
+
+/// Stands in for a span without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+#[cfg(not(feature = "tracing"))]
+impl NoSpan {
+    pub(crate) fn entered(self) -> Self {
+        self
+    }
+
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        f()
+    }
+
+    pub(crate) fn record<V>(&self, _field: &str, _value: V) -> &Self {
+        self
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use crate::{Address, Simulator, ADDRESS_LEN};
+    use std::{
+        fmt::Write as _,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    /// A recorded span or event, with its fields as `name=value` pairs.
+    #[derive(Debug)]
+    struct Node {
+        name: &'static str,
+        parent: Option<usize>,
+        fields: String,
+    }
+
+    impl Visit for Node {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    /// Records every span and event, span ids being their index plus one.
+    #[derive(Default)]
+    struct Capture {
+        spans: Mutex<Vec<Node>>,
+        events: Mutex<Vec<Node>>,
+        entered: Mutex<Vec<usize>>,
+    }
+
+    impl Capture {
+        fn current(&self) -> Option<usize> {
+            self.entered.lock().unwrap().last().copied()
+        }
+
+        /// The names of the span at `index` and its ancestors, outermost first.
+        fn path(&self, index: usize) -> String {
+            let spans = self.spans.lock().unwrap();
+            let mut path = vec![spans[index].name];
+            let mut parent = spans[index].parent;
+            while let Some(index) = parent {
+                path.push(spans[index].name);
+                parent = spans[index].parent;
+            }
+            path.reverse();
+            path.join("/")
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut node = Node {
+                name: attributes.metadata().name(),
+                parent: self.current(),
+                fields: String::new(),
+            };
+            attributes.record(&mut node);
+
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(node);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let index = span.into_u64() as usize - 1;
+            values.record(&mut self.spans.lock().unwrap()[index]);
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut node = Node {
+                name: event.metadata().name(),
+                parent: self.current(),
+                fields: String::new(),
+            };
+            event.record(&mut node);
+            self.events.lock().unwrap().push(node);
+        }
+
+        fn enter(&self, span: &Id) {
+            self.entered
+                .lock()
+                .unwrap()
+                .push(span.into_u64() as usize - 1);
+        }
+
+        fn exit(&self, _: &Id) {
+            self.entered.lock().unwrap().pop();
+        }
+    }
+
+    // `forward` passes its params on to `contract.call_contract` and returns the result,
+    // `leaf` returns `42i64`
+    const FORWARD: &str = r#"
+        (module
+            (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+            (import "contract" "call_contract" (func $call_contract (param i32 i32) (result i32)))
+            (memory (export "memory") 1)
+            (global $next (mut i32) (i32.const 1024))
+            (global $last_len (mut i32) (i32.const 0))
+            (func (export "alloc") (param $len i32) (result i32)
+                (global.set $last_len (local.get $len))
+                (global.get $next)
+                (global.set $next (i32.add (global.get $next) (local.get $len))))
+            (func (export "forward") (param $ptr i32)
+                (local $result i32)
+                (local.set $result (call $call_contract (local.get $ptr) (global.get $last_len)))
+                (call $set_call_result (local.get $result) (global.get $last_len)))
+            (func (export "leaf") (param i32)
+                (i64.store (i32.const 0) (i64.const 42))
+                (call $set_call_result (i32.const 0) (i32.const 8))))
+    "#;
+
+    /// Calls `forward` on `a`, which calls `leaf` on `b`, and returns what was recorded.
+    fn trace_nested_call() -> Arc<Capture> {
+        let simulator = Simulator::new();
+        let (a, b) = (
+            Address::new([1; ADDRESS_LEN]),
+            Address::new([2; ADDRESS_LEN]),
+        );
+        simulator
+            .create_contract(a, FORWARD.as_bytes().to_vec())
+            .unwrap();
+        simulator
+            .create_contract(b, FORWARD.as_bytes().to_vec())
+            .unwrap();
+
+        // borsh-encoded `CallContractArgs` for `leaf` on `b`
+        let mut params = b.as_bytes().to_vec();
+        params.extend(4u32.to_le_bytes());
+        params.extend(b"leaf");
+        params.extend(0u32.to_le_bytes());
+        params.extend(100_000u64.to_le_bytes());
+        params.extend(0u64.to_le_bytes());
+
+        let capture = Arc::new(Capture::default());
+        tracing::subscriber::with_default(capture.clone(), || {
+            simulator
+                .call_contract(a, "forward", &params, 1_000_000)
+                .unwrap();
+        });
+        capture
+    }
+
+    #[test]
+    fn nested_calls_are_spans_of_their_caller() {
+        let capture = trace_nested_call();
+        let len = capture.spans.lock().unwrap().len();
+        let paths: Vec<_> = (0..len).map(|index| capture.path(index)).collect();
+
+        assert_eq!(
+            paths,
+            [
+                "call",
+                "call/compile",
+                "call/instantiate",
+                "call/execute",
+                "call/execute/call",
+                "call/execute/call/compile",
+                "call/execute/call/instantiate",
+                "call/execute/call/execute",
+            ]
+        );
+
+        let spans = capture.spans.lock().unwrap();
+        let a = "01".repeat(ADDRESS_LEN);
+        let b = "02".repeat(ADDRESS_LEN);
+        assert!(
+            spans[0].fields.starts_with(&format!(
+                " contract=\"{a}\" method=\"forward\" gas=1000000 depth=0 gas_used="
+            )),
+            "{}",
+            spans[0].fields
+        );
+        assert!(
+            spans[4].fields.starts_with(&format!(
+                " contract=\"{b}\" method=\"leaf\" gas=100000 depth=1 gas_used="
+            )),
+            "{}",
+            spans[4].fields
+        );
+    }
+
+    #[test]
+    fn host_imports_record_byte_counts() {
+        let capture = trace_nested_call();
+        let events: Vec<_> = capture
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| (capture.path(event.parent.unwrap()), event.fields.clone()))
+            .collect();
+
+        // `CallContractArgs` for `leaf`, and `Ok` followed by `leaf`'s 8 byte result
+        let args_len = ADDRESS_LEN + 4 + "leaf".len() + 4 + 8 + 8;
+        let result_len = 1 + 8;
+
+        assert_eq!(
+            events,
+            [
+                (
+                    "call/execute/call/execute".to_string(),
+                    " import=\"contract.set_call_result\" bytes_in=8".to_string()
+                ),
+                (
+                    "call/execute".to_string(),
+                    format!(
+                        " import=\"contract.call_contract\" bytes_in={args_len} bytes_out={result_len}"
+                    )
+                ),
+                (
+                    "call/execute".to_string(),
+                    format!(" import=\"contract.set_call_result\" bytes_in={result_len}")
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,9 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use simulator::Address;
+
+fn main() {
+    // short byte vectors must go through the fallible `TryFrom`
+    let _: Address = vec![1u8, 2, 3].into();
+}
@@ -0,0 +1,9 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use simulator::Address;
+
+fn main() {
+    // the `Vec<u8>` constructor is gone, `Address::try_from` checks the length instead
+    let _ = Address::new(vec![0u8; 33]);
+}
@@ -37,14 +37,8 @@ pub fn call_with_param_external(
     max_units: Gas,
     value: i64,
 ) -> i64 {
-    ctx.call_contract(
-        target,
-        "call_with_param",
-        &value.to_le_bytes(),
-        max_units,
-        0,
-    )
-    .unwrap()
+    ctx.call_typed(target, "call_with_param", &value, max_units, 0)
+        .unwrap()
 }
 
 #[public]
@@ -60,11 +54,12 @@ pub fn call_with_two_params_external(
     value1: i64,
     value2: i64,
 ) -> i64 {
-    let args: Vec<_> = value1
-        .to_le_bytes()
-        .into_iter()
-        .chain(value2.to_le_bytes())
-        .collect();
-    ctx.call_contract(target, "call_with_two_params", &args, max_units, 0)
-        .unwrap()
+    ctx.call_typed(
+        target,
+        "call_with_two_params",
+        &(value1, value2),
+        max_units,
+        0,
+    )
+    .unwrap()
 }
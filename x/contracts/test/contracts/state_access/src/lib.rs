@@ -24,3 +24,22 @@ pub fn get(context: &mut Context) -> Option<i64> {
 pub fn delete(context: &mut Context) -> Option<i64> {
     context.delete(State).expect("failed to get state")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmlanche::Address;
+
+    #[test]
+    fn put_get_delete_round_trip() {
+        let mut context = Context::with_actor(Address::default());
+        assert_eq!(get(&mut context), None);
+
+        put(&mut context, -7);
+        assert_eq!(get(&mut context), Some(-7));
+
+        assert_eq!(delete(&mut context), Some(-7));
+        assert_eq!(get(&mut context), None);
+        assert_eq!(delete(&mut context), None);
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Lenient decoding of `#[public]` arguments.
+//! These are called from code generated by the macro and are not meant to be used directly.
+
+use borsh::{
+    io::{Error, ErrorKind, Result},
+    BorshDeserialize,
+};
+
+/// Decodes a trailing argument, falling back to `default` if the input is exhausted.
+/// Input that ends part-way through the argument is still an error.
+///
+/// # Errors
+/// Returns an error if the remaining bytes are not a valid encoding of `T`.
+pub fn decode_trailing_arg<T: BorshDeserialize>(
+    buf: &mut &[u8],
+    default: impl FnOnce() -> T,
+) -> Result<T> {
+    if buf.is_empty() {
+        Ok(default())
+    } else {
+        T::deserialize(buf)
+    }
+}
+
+/// Mirrors the check in [`borsh::from_slice`] that every byte of the input was consumed.
+///
+/// # Errors
+/// Returns an error if `buf` is not empty.
+pub fn finish_args(buf: &[u8]) -> Result<()> {
+    if buf.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::new(ErrorKind::InvalidData, "Not all bytes read"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_input_uses_default() {
+        let mut buf: &[u8] = &[];
+        let value = decode_trailing_arg::<Option<u64>>(&mut buf, || None).unwrap();
+        assert_eq!(value, None);
+
+        let value = decode_trailing_arg::<u64>(&mut buf, || 7).unwrap();
+        assert_eq!(value, 7);
+    }
+
+    #[test]
+    fn present_input_is_decoded() {
+        let bytes = borsh::to_vec(&(Some(5u64), 9u32)).unwrap();
+        let mut buf = bytes.as_slice();
+
+        let first = decode_trailing_arg::<Option<u64>>(&mut buf, || None).unwrap();
+        let second = decode_trailing_arg::<u32>(&mut buf, || 0).unwrap();
+
+        assert_eq!((first, second), (Some(5), 9));
+        assert!(finish_args(buf).is_ok());
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let bytes = 5u64.to_le_bytes();
+        let mut buf = &bytes[..3];
+
+        assert!(decode_trailing_arg::<u64>(&mut buf, || 0).is_err());
+    }
+
+    #[test]
+    fn leftover_input_is_an_error() {
+        assert!(finish_args(&[1]).is_err());
+    }
+}
@@ -9,6 +9,8 @@ use crate::{
     types::{Address, ContractId},
     Gas, Id,
 };
+#[cfg(feature = "simulator-imports")]
+use alloc::string::String;
 use alloc::{boxed::Box, vec::Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
 use displaydoc::Display;
@@ -23,13 +25,14 @@ pub struct Context {
     height: u64,
     timestamp: u64,
     action_id: Id,
-    state_cache: Cache,
+    pub(crate) state_cache: Cache,
     host_accessor: Accessor,
 }
 
 #[cfg(feature = "debug")]
 mod debug {
     use super::Context;
+    use crate::fmt::HexSlice;
     use core::fmt::{Debug, Formatter, Result};
 
     macro_rules! debug_struct_fields {
@@ -52,6 +55,8 @@ mod debug {
                 host_accessor: _,
             } = self;
 
+            let action_id = &HexSlice(action_id);
+
             debug_struct_fields!(
                 f,
                 Context,
@@ -125,6 +130,28 @@ impl Context {
         self.timestamp
     }
 
+    /// Returns the height and timestamp of the block being executed
+    #[must_use]
+    pub fn block_info(&self) -> BlockInfo {
+        BlockInfo {
+            height: self.height,
+            timestamp: self.timestamp,
+        }
+    }
+
+    #[cfg(feature = "test")]
+    /// Sets the block-height and block-timestamp of the context
+    pub fn set_block_info(&mut self, block: BlockInfo) {
+        self.height = block.height;
+        self.timestamp = block.timestamp;
+    }
+
+    /// Returns the block-timestamp as a [`Timestamp`](crate::time::Timestamp)
+    #[must_use]
+    pub fn time(&self) -> crate::time::Timestamp {
+        crate::time::Timestamp::from_secs(self.timestamp)
+    }
+
     /// Returns the action-id
     /// # Panics
     /// Panics if the context was not injected
@@ -230,6 +257,105 @@ impl Context {
         borsh::from_slice(&bytes).expect("failed to deserialize the result")
     }
 
+    #[cfg(feature = "simulator-imports")]
+    /// Emits an event named `name` carrying the borsh-encoded `data`.
+    /// The host records it with the actor and the current block.
+    /// Only the simulator provides this import, see the `simulator-imports` feature.
+    /// # Panics
+    /// Panics if `data` cannot be serialized
+    #[inline]
+    pub fn emit_event<T: BorshSerialize>(&mut self, name: &str, data: &T) {
+        let data = borsh::to_vec(data).expect("failed to serialize the event data");
+        let ptr = borsh::to_vec(&(name, data)).expect("failed to serialize args");
+        self.host_accessor.emit_event(&ptr);
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Encodes `data` as a bech32 string with the human-readable part `hrp`.
+    /// Only the simulator provides this import, see the `simulator-imports` feature.
+    /// # Errors
+    /// Returns a [`Bech32Error`] if `hrp` is invalid or the string would be too long.
+    /// # Panics
+    /// Panics if there was an issue deserializing the result
+    #[inline]
+    pub fn bech32_encode(&self, hrp: &str, data: &[u8]) -> Result<String, Bech32Error> {
+        let ptr = borsh::to_vec(&(hrp, data)).expect("failed to serialize args");
+        let bytes = self.host_accessor.bech32_encode(&ptr);
+
+        borsh::from_slice(&bytes).expect("failed to deserialize the result")
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Decodes the bech32 string `encoded`, which must have the human-readable part `hrp`.
+    /// Only the simulator provides this import, see the `simulator-imports` feature.
+    /// # Errors
+    /// Returns a [`Bech32Error`] if `encoded` is not valid bech32 or has a different human-readable part.
+    /// # Panics
+    /// Panics if there was an issue deserializing the result
+    #[inline]
+    pub fn bech32_decode(&self, hrp: &str, encoded: &str) -> Result<Vec<u8>, Bech32Error> {
+        let ptr = borsh::to_vec(&(hrp, encoded)).expect("failed to serialize args");
+        let bytes = self.host_accessor.bech32_decode(&ptr);
+
+        borsh::from_slice(&bytes).expect("failed to deserialize the result")
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Counts the keys starting with the raw bytes `prefix`, in a single host call.
+    /// Only the simulator provides this import, like the other prefix folds,
+    /// see the `simulator-imports` feature.
+    /// # Panics
+    /// Panics if there was an issue deserializing the result
+    #[must_use]
+    #[inline]
+    pub fn count_prefix(&mut self, prefix: &[u8]) -> u64 {
+        self.fold_prefix(prefix, FoldOp::Count)
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Adds up the `u64` values of the keys starting with the raw bytes `prefix`, in a single host call.
+    /// # Errors
+    /// Returns a [`FoldError`] if a value is not a `u64` or the sum overflows.
+    /// # Panics
+    /// Panics if there was an issue deserializing the result
+    #[inline]
+    pub fn sum_prefix_u64(&mut self, prefix: &[u8]) -> Result<u64, FoldError> {
+        self.fold_prefix(prefix, FoldOp::SumU64)
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Finds the key starting with the raw bytes `prefix` that has the largest `u64` value,
+    /// the first one in key order on ties, in a single host call.
+    /// # Errors
+    /// Returns a [`FoldError`] if a value is not a `u64`.
+    /// # Panics
+    /// Panics if there was an issue deserializing the result
+    #[inline]
+    pub fn max_prefix_u64(&mut self, prefix: &[u8]) -> Result<Option<(Vec<u8>, u64)>, FoldError> {
+        self.fold_prefix(prefix, FoldOp::MaxU64)
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Collects the first `limit` keys starting with the raw bytes `prefix` in ascending order,
+    /// in a single host call.
+    /// # Panics
+    /// Panics if there was an issue deserializing the result
+    #[must_use]
+    #[inline]
+    pub fn prefix_keys(&mut self, prefix: &[u8], limit: u32) -> Vec<Vec<u8>> {
+        self.fold_prefix(prefix, FoldOp::Keys { limit })
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    fn fold_prefix<T: BorshDeserialize>(&mut self, prefix: &[u8], op: FoldOp) -> T {
+        // the host folds over storage, so it has to see the cached writes
+        self.state_cache.flush();
+
+        let bytes = self.host_accessor.fold_prefix(prefix, op.code(), &op.arg());
+
+        borsh::from_slice(&bytes).expect("failed to deserialize the result")
+    }
+
     /// Attempts to call a function `name` with `args` on the given contract. This method
     /// is used to call functions on external contracts.
     /// # Errors
@@ -260,6 +386,25 @@ impl Context {
         borsh::from_slice(&bytes).expect("failed to deserialize")
     }
 
+    /// Like [`Context::call_contract`], but borsh-encodes `args` the way `#[public]` functions
+    /// decode their parameters, so a tuple of the callee's parameters can be passed as is.
+    /// # Errors
+    /// Returns a [`ExternalCallError`] if the call fails.
+    /// # Panics
+    /// Will panic if the args cannot be serialized or the result is not an `R`
+    #[inline]
+    pub fn call_typed<A: BorshSerialize, R: BorshDeserialize>(
+        &mut self,
+        address: Address,
+        function_name: &str,
+        args: &A,
+        max_units: Gas,
+        value: u64,
+    ) -> Result<R, ExternalCallError> {
+        let args = borsh::to_vec(args).expect("failed to serialize args");
+        self.call_contract(address, function_name, &args, max_units, value)
+    }
+
     #[cfg(feature = "bindings")]
     #[must_use]
     pub fn to_extern(&mut self, args: ExternalCallArgs) -> ExternalCallContext<'_, Self> {
@@ -341,6 +486,63 @@ impl Context {
     pub fn mock_set_balance(&self, account: Address, balance: u64) {
         self.host_accessor.set_balance(account, balance);
     }
+
+    /// Sets the gas returned by [`Context::remaining_fuel`].
+    pub fn mock_remaining_fuel(&self, fuel: Gas) {
+        self.host_accessor.set_remaining_fuel(fuel);
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Returns the name and data of every event emitted with [`Context::emit_event`], oldest first.
+    #[must_use]
+    pub fn emitted_events(&self) -> Vec<(String, Vec<u8>)> {
+        self.host_accessor.events()
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Mocks the result of [`Context::bech32_encode`] for `hrp` and `data`.
+    /// # Panics
+    /// Panics if serialization fails.
+    pub fn mock_bech32_encode(&self, hrp: &str, data: &[u8], result: Result<String, Bech32Error>) {
+        use crate::host::BECH32_ENCODE_PREFIX;
+
+        let key =
+            borsh::to_vec(&(BECH32_ENCODE_PREFIX, hrp, data)).expect("failed to serialize args");
+        let result = borsh::to_vec(&result).expect("error serializing result");
+        self.host_accessor.state().put(&key, result);
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Mocks the result of [`Context::bech32_decode`] for `hrp` and `encoded`.
+    /// # Panics
+    /// Panics if serialization fails.
+    pub fn mock_bech32_decode(
+        &self,
+        hrp: &str,
+        encoded: &str,
+        result: Result<Vec<u8>, Bech32Error>,
+    ) {
+        use crate::host::BECH32_DECODE_PREFIX;
+
+        let key =
+            borsh::to_vec(&(BECH32_DECODE_PREFIX, hrp, encoded)).expect("failed to serialize args");
+        let result = borsh::to_vec(&result).expect("error serializing result");
+        self.host_accessor.state().put(&key, result);
+    }
+
+    #[cfg(feature = "simulator-imports")]
+    /// Mocks the result of folding over `prefix` with `op`, as returned by [`Context::count_prefix`],
+    /// [`Context::sum_prefix_u64`], [`Context::max_prefix_u64`] or [`Context::prefix_keys`].
+    /// # Panics
+    /// Panics if serialization fails.
+    pub fn mock_fold_prefix<T: BorshSerialize>(&self, prefix: &[u8], op: FoldOp, result: T) {
+        use crate::host::FOLD_PREFIX_PREFIX;
+
+        let key = borsh::to_vec(&(FOLD_PREFIX_PREFIX, prefix, op.code(), op.arg()))
+            .expect("failed to serialize args");
+        let result = borsh::to_vec(&result).expect("error serializing result");
+        self.host_accessor.state().put(&key, result);
+    }
 }
 
 /// An error that is returned from call to public functions.
@@ -359,6 +561,74 @@ pub enum ExternalCallError {
     InsufficientFunds = 3,
 }
 
+#[cfg(feature = "simulator-imports")]
+/// An error returned by [`Context::bech32_encode`] and [`Context::bech32_decode`].
+#[derive(Debug, Display, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+#[repr(u8)]
+#[non_exhaustive]
+#[borsh(use_discriminant = true)]
+pub enum Bech32Error {
+    /// the human-readable part is empty, too long, or not ASCII
+    InvalidHrp = 0,
+    /// the string contains a character outside the bech32 alphabet
+    InvalidCharacter = 1,
+    /// the string mixes upper and lower case
+    MixedCase = 2,
+    /// the checksum does not match the string
+    InvalidChecksum = 3,
+    /// the string has a different human-readable part
+    WrongHrp = 4,
+    /// the string is too short or too long
+    InvalidLength = 5,
+}
+
+#[cfg(feature = "simulator-imports")]
+/// An aggregate the host computes over every key with a prefix, see [`Context::count_prefix`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoldOp {
+    Count,
+    SumU64,
+    MaxU64,
+    Keys { limit: u32 },
+}
+
+#[cfg(feature = "simulator-imports")]
+impl FoldOp {
+    fn code(self) -> u32 {
+        match self {
+            Self::Count => 0,
+            Self::SumU64 => 1,
+            Self::MaxU64 => 2,
+            Self::Keys { .. } => 3,
+        }
+    }
+
+    fn arg(self) -> Vec<u8> {
+        match self {
+            Self::Keys { limit } => limit.to_le_bytes().to_vec(),
+            Self::Count | Self::SumU64 | Self::MaxU64 => Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "simulator-imports")]
+/// An error returned by [`Context::sum_prefix_u64`] and [`Context::max_prefix_u64`].
+#[derive(Debug, Display, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FoldError {
+    /// the value under the key {key:?} is not a `u64`
+    InvalidValue { key: Vec<u8> },
+    /// the sum does not fit in a `u64`
+    Overflow,
+}
+
+/// The block a contract is executing in, see [`Context::block_info`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BlockInfo {
+    pub height: u64,
+    pub timestamp: u64,
+}
+
 /// Arguments for an external call.
 #[cfg_attr(feature = "debug", derive(Debug))]
 #[derive(Clone, Copy)]
@@ -0,0 +1,169 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Checks that let a contract reject work it cannot finish before doing it.
+//!
+//! [`borsh_size_of`] gives the encoded size of a value without encoding it, and
+//! [`Context::can_afford_state_write`] and [`Context::can_afford_return`] compare that against
+//! the state limits and the gas the host charges, so a contract can revert cleanly instead of
+//! running out of gas or writing an oversized value after doing all of its work.
+//!
+//! The host charges a flat fee per import, whatever the size of its arguments, so only the value
+//! length matters to these checks and neither takes the key or result length. The `memo` example
+//! contract uses them to reject oversized input.
+//!
+//! ## Example
+//! ```
+//! use wasmlanche::{ensure, estimate::borsh_size_of, public, state_schema, Context, ErrorCode, Revert};
+//!
+//! const TOO_LARGE: ErrorCode = ErrorCode::new(1, "memo does not fit in state");
+//!
+//! state_schema! {
+//!     Memo => Vec<String>,
+//! }
+//!
+//! #[public]
+//! pub fn set_memo(context: &mut Context, lines: Vec<String>) -> Result<(), Revert> {
+//!     ensure!(context.can_afford_state_write(borsh_size_of(&lines)), TOO_LARGE);
+//!     context.store_by_key(Memo, lines).expect("failed to serialize the memo");
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use crate::Gas;
+use borsh::{io, BorshSerialize};
+
+/// The bytes in each chunk of a stored value.
+pub const CHUNK_SIZE: usize = 64;
+
+/// The largest value that can be stored under a key from [`state_schema!`](crate::state_schema).
+/// A value of `len` bytes takes `len / CHUNK_SIZE + 1` chunks, and those keys allow four.
+pub const MAX_STATE_VALUE_SIZE: usize =
+    u16::from_be_bytes(crate::state::STATE_MAX_CHUNKS) as usize * CHUNK_SIZE - 1;
+
+/// The gas the host charges to write state, which a contract's changes are flushed with in one call.
+/// The same for every write, like the runtime's `putCost` and the simulator's charge for `state.put`.
+pub const STATE_WRITE_COST: Gas = 10_000;
+
+/// The gas the host charges to set the result of a call, whatever its length.
+pub const SET_RESULT_COST: Gas = 10_000;
+
+/// Returns the number of bytes `value` is borsh-encoded to, without allocating them.
+/// # Panics
+/// Panics if `value` cannot be serialized, in which case encoding it would fail too.
+#[must_use]
+pub fn borsh_size_of<T: BorshSerialize + ?Sized>(value: &T) -> usize {
+    let mut counter = Counter(0);
+    value
+        .serialize(&mut counter)
+        .expect("failed to serialize the value");
+    counter.0
+}
+
+struct Counter(usize);
+
+impl io::Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl crate::Context {
+    /// Whether a value of `value_len` bytes can be stored under a [`state_schema!`](crate::state_schema)
+    /// key, and the contract has the gas left to write it.
+    /// The key's length is not needed, schema keys have room for any value up to [`MAX_STATE_VALUE_SIZE`]
+    /// and the write costs [`STATE_WRITE_COST`] regardless.
+    #[must_use]
+    pub fn can_afford_state_write(&self, value_len: usize) -> bool {
+        value_len <= MAX_STATE_VALUE_SIZE && self.remaining_fuel() >= STATE_WRITE_COST
+    }
+
+    /// Whether the contract has the gas left to set its result, which costs [`SET_RESULT_COST`] at any length.
+    #[must_use]
+    pub fn can_afford_return(&self) -> bool {
+        self.remaining_fuel() >= SET_RESULT_COST
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Address, ErrorCode, Revert};
+    use borsh::BorshDeserialize;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    enum Action {
+        Transfer { to: Address, amount: u64 },
+        Memo(String),
+        Burn,
+    }
+
+    fn assert_size<T: BorshSerialize>(value: &T) {
+        assert_eq!(borsh_size_of(value), borsh::to_vec(value).unwrap().len());
+    }
+
+    #[test]
+    fn agrees_with_encoding() {
+        assert_size(&());
+        assert_size(&true);
+        assert_size(&u8::MAX);
+        assert_size(&u64::MAX);
+        assert_size(&i128::MIN);
+        assert_size(&String::from("hello, world"));
+        assert_size(&Address::default());
+        assert_size(&[7u8; 33]);
+        assert_size(&vec![1u32, 2, 3]);
+        assert_size(&Vec::<u8>::new());
+        assert_size(&Some(5u16));
+        assert_size(&None::<u16>);
+        assert_size(&(1u8, String::from("a"), vec![Some(2u64), None]));
+        assert_size(&BTreeMap::from([
+            (1u8, String::from("a")),
+            (2, String::new()),
+        ]));
+        assert_size(&BTreeSet::from([Address::default()]));
+        assert_size(&Ok::<u64, Revert>(1));
+        assert_size(&Err::<u64, Revert>(ErrorCode::new(3, "no").into()));
+        assert_size(&vec![
+            Action::Transfer {
+                to: Address::default(),
+                amount: 10,
+            },
+            Action::Memo(String::from("hi")),
+            Action::Burn,
+        ]);
+    }
+
+    #[cfg(feature = "test")]
+    #[test]
+    fn checks_gas_and_size() {
+        let context = crate::Context::with_actor(Address::default());
+        assert!(context.can_afford_state_write(MAX_STATE_VALUE_SIZE));
+        assert!(!context.can_afford_state_write(MAX_STATE_VALUE_SIZE + 1));
+        assert!(context.can_afford_return());
+
+        context.mock_remaining_fuel(STATE_WRITE_COST - 1);
+        assert!(!context.can_afford_state_write(0));
+        assert!(!context.can_afford_return());
+    }
+
+    #[test]
+    fn costs_match_the_simulator() {
+        assert_eq!(STATE_WRITE_COST, simulator::STATE_WRITE_COST);
+        assert_eq!(SET_RESULT_COST, simulator::SET_RESULT_COST);
+    }
+
+    #[test]
+    fn max_state_value_fills_the_chunks() {
+        assert_eq!(MAX_STATE_VALUE_SIZE, 255);
+        assert_eq!(MAX_STATE_VALUE_SIZE / CHUNK_SIZE + 1, 4);
+    }
+}
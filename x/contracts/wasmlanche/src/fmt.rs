@@ -0,0 +1,171 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Display adapters for byte values.
+//! Use these instead of printing raw `[u8]`/`Vec<u8>` debug output so that keys, addresses,
+//! and secrets look the same everywhere they are logged.
+
+use core::fmt::{Debug, Display, Formatter, LowerHex, Result};
+
+/// Formats bytes as lowercase hex without a prefix.
+#[derive(Clone, Copy)]
+pub struct HexSlice<'a>(pub &'a [u8]);
+
+impl LowerHex for HexSlice<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{byte:02x}"))
+    }
+}
+
+impl Display for HexSlice<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        LowerHex::fmt(self, f)
+    }
+}
+
+impl Debug for HexSlice<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        LowerHex::fmt(self, f)
+    }
+}
+
+/// Formats bytes as hex, keeping only the first and last `edge` bytes of long values,
+/// e.g. `00fc793c..2b166e8b`.
+#[derive(Clone, Copy)]
+pub struct ShortHex<'a> {
+    bytes: &'a [u8],
+    edge: usize,
+}
+
+impl<'a> ShortHex<'a> {
+    /// Number of bytes kept on each side by [`ShortHex::new`].
+    pub const DEFAULT_EDGE: usize = 4;
+
+    #[must_use]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_edge(bytes, Self::DEFAULT_EDGE)
+    }
+
+    /// Keeps `edge` bytes on each side of the elision.
+    #[must_use]
+    pub fn with_edge(bytes: &'a [u8], edge: usize) -> Self {
+        Self { bytes, edge }
+    }
+}
+
+impl Display for ShortHex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let Self { bytes, edge } = *self;
+
+        if bytes.len() <= edge * 2 {
+            return write!(f, "{}", HexSlice(bytes));
+        }
+
+        let (head, tail) = (&bytes[..edge], &bytes[bytes.len() - edge..]);
+        write!(f, "{}..{}", HexSlice(head), HexSlice(tail))
+    }
+}
+
+impl Debug for ShortHex<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        Display::fmt(self, f)
+    }
+}
+
+/// Wraps a secret so that it is never printed.
+/// Both [`Debug`] and [`Display`] print `[REDACTED len=N]`; use [`AsRef`] or
+/// [`Sensitive::into_inner`] to get at the value.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Sensitive<T>(T);
+
+impl<T> Sensitive<T> {
+    #[must_use]
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Sensitive<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for Sensitive<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl<T: AsRef<[u8]>> Display for Sensitive<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "[REDACTED len={}]", self.0.as_ref().len())
+    }
+}
+
+impl<T: AsRef<[u8]>> Debug for Sensitive<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_slice() {
+        assert_eq!(HexSlice(&[]).to_string(), "");
+        assert_eq!(HexSlice(&[0, 1, 0xab, 0xff]).to_string(), "0001abff");
+        assert_eq!(format!("{:?}", HexSlice(&[0x0f])), "0f");
+    }
+
+    #[test]
+    fn short_hex_elides_long_values() {
+        let bytes: Vec<u8> = (0..33).collect();
+
+        assert_eq!(ShortHex::new(&bytes).to_string(), "00010203..1d1e1f20");
+        assert_eq!(ShortHex::with_edge(&bytes, 1).to_string(), "00..20");
+    }
+
+    #[test]
+    fn short_hex_keeps_short_values() {
+        assert_eq!(
+            ShortHex::new(&[1, 2, 3, 4, 5, 6, 7, 8]).to_string(),
+            "0102030405060708"
+        );
+        assert_eq!(ShortHex::with_edge(&[1, 2], 1).to_string(), "0102");
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn address_debug_is_short_hex() {
+        let address = crate::Address::new([0xab; 33]);
+        assert_eq!(format!("{address:?}"), "Address(abababab..abababab)");
+    }
+
+    #[test]
+    fn sensitive_is_redacted() {
+        let secret = Sensitive::new([7u8; 32]);
+
+        assert_eq!(secret.to_string(), "[REDACTED len=32]");
+        assert_eq!(format!("{secret:?}"), "[REDACTED len=32]");
+        assert_eq!(
+            format!("signing failed: {:?}", (&secret, 1)),
+            "signing failed: ([REDACTED len=32], 1)"
+        );
+    }
+
+    #[test]
+    fn sensitive_round_trips() {
+        let secret = Sensitive::from(vec![1u8, 2, 3]);
+
+        assert_eq!(secret.as_ref(), &[1, 2, 3]);
+        assert_eq!(secret.into_inner(), vec![1, 2, 3]);
+    }
+}
@@ -63,6 +63,12 @@ mod test_wrappers {
     pub const SEND_PREFIX: u8 = 1;
     pub const CALL_FUNCTION_PREFIX: u8 = 2;
     pub const DEPLOY_PREFIX: u8 = 3;
+    #[cfg(feature = "simulator-imports")]
+    pub const BECH32_ENCODE_PREFIX: u8 = 4;
+    #[cfg(feature = "simulator-imports")]
+    pub const BECH32_DECODE_PREFIX: u8 = 5;
+    #[cfg(feature = "simulator-imports")]
+    pub const FOLD_PREFIX_PREFIX: u8 = 6;
 
     impl StateAccessor {
         pub fn put(_args: &[u8]) {
@@ -173,6 +179,21 @@ mod test_wrappers {
             self.state().get_fuel()
         }
 
+        pub fn set_remaining_fuel(&self, fuel: Gas) {
+            self.state().fuel.set(fuel);
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        pub fn emit_event(&self, args: &[u8]) {
+            let event = borsh::from_slice(args).expect("failed to deserialize the event");
+            self.state.events.borrow_mut().push(event);
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        pub fn events(&self) -> Vec<(String, Vec<u8>)> {
+            self.state.events.borrow().clone()
+        }
+
         pub fn send_value(&self, args: &[u8]) -> HostPtr {
             // send prefix + key
             let key = [SEND_PREFIX]
@@ -189,6 +210,54 @@ mod test_wrappers {
 
             host_ptr
         }
+
+        #[cfg(feature = "simulator-imports")]
+        pub fn bech32_encode(&self, args: &[u8]) -> HostPtr {
+            let key = [BECH32_ENCODE_PREFIX]
+                .iter()
+                .chain(args.iter())
+                .copied()
+                .collect::<Vec<u8>>();
+
+            let host_ptr = self.state.get(&key);
+            assert!(
+                !host_ptr.is_null(),
+                "bech32_encode not mocked. Please mock the function call."
+            );
+
+            host_ptr
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        pub fn bech32_decode(&self, args: &[u8]) -> HostPtr {
+            let key = [BECH32_DECODE_PREFIX]
+                .iter()
+                .chain(args.iter())
+                .copied()
+                .collect::<Vec<u8>>();
+
+            let host_ptr = self.state.get(&key);
+            assert!(
+                !host_ptr.is_null(),
+                "bech32_decode not mocked. Please mock the function call."
+            );
+
+            host_ptr
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        pub fn fold_prefix(&self, prefix: &[u8], op: u32, arg: &[u8]) -> HostPtr {
+            let key = borsh::to_vec(&(FOLD_PREFIX_PREFIX, prefix, op, arg))
+                .expect("failed to serialize args");
+
+            let host_ptr = self.state.get(&key);
+            assert!(
+                !host_ptr.is_null(),
+                "fold_prefix not mocked. Please mock the function call."
+            );
+
+            host_ptr
+        }
     }
 
     impl Default for MockState {
@@ -201,7 +270,9 @@ mod test_wrappers {
     pub struct MockState {
         state: RefCell<hashbrown::HashMap<Vec<u8>, Vec<u8>>>,
         deploys: Cell<u8>,
-        fuel: Gas,
+        fuel: Cell<Gas>,
+        #[cfg(feature = "simulator-imports")]
+        events: RefCell<Vec<(String, Vec<u8>)>>,
     }
 
     impl MockState {
@@ -209,7 +280,9 @@ mod test_wrappers {
             Self {
                 state: RefCell::new(hashbrown::HashMap::new()),
                 deploys: Cell::new(0),
-                fuel: u64::MAX,
+                fuel: Cell::new(u64::MAX),
+                #[cfg(feature = "simulator-imports")]
+                events: RefCell::default(),
             }
         }
 
@@ -236,7 +309,7 @@ mod test_wrappers {
         }
 
         pub fn get_fuel(&self) -> HostPtr {
-            let fuel_bytes = borsh::to_vec(&self.fuel).expect("failed to serialize");
+            let fuel_bytes = borsh::to_vec(&self.fuel.get()).expect("failed to serialize");
             let ptr = crate::memory::alloc(fuel_bytes.len());
             unsafe {
                 std::ptr::copy(
@@ -350,5 +423,59 @@ mod external_wrappers {
 
             unsafe { send_value(args.as_ptr(), args.len()) }
         }
+
+        #[cfg(feature = "simulator-imports")]
+        #[inline]
+        pub fn emit_event(&self, args: &[u8]) {
+            #[link(wasm_import_module = "contract")]
+            extern "C" {
+                #[link_name = "emit_event"]
+                fn emit_event(ptr: *const u8, len: usize);
+            }
+
+            unsafe { emit_event(args.as_ptr(), args.len()) }
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        #[inline]
+        pub fn bech32_encode(&self, args: &[u8]) -> HostPtr {
+            #[link(wasm_import_module = "addr")]
+            extern "C" {
+                #[link_name = "bech32_encode"]
+                fn bech32_encode(ptr: *const u8, len: usize) -> HostPtr;
+            }
+
+            unsafe { bech32_encode(args.as_ptr(), args.len()) }
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        #[inline]
+        pub fn bech32_decode(&self, args: &[u8]) -> HostPtr {
+            #[link(wasm_import_module = "addr")]
+            extern "C" {
+                #[link_name = "bech32_decode"]
+                fn bech32_decode(ptr: *const u8, len: usize) -> HostPtr;
+            }
+
+            unsafe { bech32_decode(args.as_ptr(), args.len()) }
+        }
+
+        #[cfg(feature = "simulator-imports")]
+        #[inline]
+        pub fn fold_prefix(&self, prefix: &[u8], op: u32, arg: &[u8]) -> HostPtr {
+            #[link(wasm_import_module = "state")]
+            extern "C" {
+                #[link_name = "fold_prefix"]
+                fn fold_prefix(
+                    prefix_ptr: *const u8,
+                    prefix_len: usize,
+                    op: u32,
+                    arg_ptr: *const u8,
+                    arg_len: usize,
+                ) -> HostPtr;
+            }
+
+            unsafe { fold_prefix(prefix.as_ptr(), prefix.len(), op, arg.as_ptr(), arg.len()) }
+        }
     }
 }
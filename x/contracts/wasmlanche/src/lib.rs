@@ -56,8 +56,17 @@
 //! ## Hint
 //! Use the [dbg!] macro when testing your contract, along with the `-- --nocapture` argument to your `cargo test` command.
 
+#[doc(hidden)]
+pub mod args;
 #[cfg(feature = "build")]
 pub mod build;
+pub mod estimate;
+pub mod fmt;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pause;
+pub mod random;
+pub mod revert;
+pub mod time;
 
 #[cfg(all(feature = "simulator", not(target_arch = "wasm32")))]
 pub mod simulator;
@@ -91,11 +100,20 @@ pub use self::context::ExternalCallContext;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::{
-    context::{Context, ExternalCallArgs, ExternalCallError},
+    context::{BlockInfo, Context, ExternalCallArgs, ExternalCallError},
     state::{macro_types, Error},
 };
 
-pub use self::types::{Address, ContractId, Gas, Id, ID_LEN};
+#[cfg(all(feature = "simulator-imports", not(target_arch = "wasm32")))]
+pub use self::context::{Bech32Error, FoldError, FoldOp};
+
+pub use self::{
+    revert::{ErrorCode, Revert},
+    types::{Address, ContractId, Gas, Id, ID_LEN},
+};
+
+#[doc(hidden)]
+pub use self::types::DispatchResult;
 
 #[doc(hidden)]
 #[cfg(not(target_arch = "wasm32"))]
@@ -118,10 +136,9 @@ impl Context {
 #[cfg(target_arch = "wasm32")]
 pub type HostPtr = u32;
 
-#[cfg(target_arch = "wasm32")]
 pub use self::logging::{log, register_panic};
 
-pub use sdk_macros::{public, state_schema};
+pub use sdk_macros::{public, public_dispatch, state_schema, when_not_paused};
 
 // re-exports
 pub use borsh;
@@ -0,0 +1,120 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! An emergency stop for contracts.
+//!
+//! The contract's owner calls [`Context::pause`] to stop every function marked with
+//! [`#[when_not_paused]`](crate::when_not_paused), which then revert with [`PAUSED`], and
+//! [`Context::unpause`] to resume them. Functions without the attribute, such as the one that
+//! unpauses the contract, keep working while it is paused.
+//!
+//! The flag is kept in the contract's own state. Only the owner's calls change it, so a
+//! rejected [`Context::pause`] leaves the contract as it was.
+
+use crate::{ensure, state::STATE_MAX_CHUNKS, Address, Context, ErrorCode, Revert};
+
+/// Returned by [`#[when_not_paused]`](crate::when_not_paused) functions while the contract is paused.
+pub const PAUSED: ErrorCode = ErrorCode::new(0xffff_0000, "contract is paused");
+/// Returned by [`Context::pause`] and [`Context::unpause`] when the actor is not the owner.
+pub const NOT_OWNER: ErrorCode = ErrorCode::new(
+    0xffff_0001,
+    "only the owner may pause or unpause the contract",
+);
+
+/// Laid out like the keys [`state_schema!`](crate::state_schema) produces: the prefix `0xff`, then
+/// `b"paused"` and [`STATE_MAX_CHUNKS`]. `state_schema!` rejects a 256th key, so no schema key uses
+/// that prefix.
+pub(crate) const PAUSED_KEY: &[u8] = &[
+    0xff,
+    b'p',
+    b'a',
+    b'u',
+    b's',
+    b'e',
+    b'd',
+    STATE_MAX_CHUNKS[0],
+    STATE_MAX_CHUNKS[1],
+];
+
+impl Context {
+    /// Whether [`Context::pause`] has been called without a matching [`Context::unpause`].
+    /// # Panics
+    /// Panics if the stored flag cannot be deserialized.
+    #[must_use]
+    pub fn is_paused(&mut self) -> bool {
+        self.state_cache
+            .get_with_raw_key(PAUSED_KEY)
+            .expect("failed to read the pause flag")
+            .unwrap_or(false)
+    }
+
+    /// Stops every [`#[when_not_paused]`](crate::when_not_paused) function until [`Context::unpause`] is called.
+    /// # Errors
+    /// Reverts with [`NOT_OWNER`] unless the actor is `owner`.
+    pub fn pause(&mut self, owner: Address) -> Result<(), Revert> {
+        self.set_paused(owner, true)
+    }
+
+    /// Resumes the functions stopped by [`Context::pause`].
+    /// # Errors
+    /// Reverts with [`NOT_OWNER`] unless the actor is `owner`.
+    pub fn unpause(&mut self, owner: Address) -> Result<(), Revert> {
+        self.set_paused(owner, false)
+    }
+
+    fn set_paused(&mut self, owner: Address, paused: bool) -> Result<(), Revert> {
+        ensure!(self.actor() == owner, NOT_OWNER);
+        self.state_cache.store_raw(PAUSED_KEY, [u8::from(paused)].to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    const OWNER: Address = Address::ZERO;
+
+    #[test]
+    fn owner_pauses_and_unpauses() {
+        let mut context = Context::with_actor(OWNER);
+        assert!(!context.is_paused());
+
+        context.pause(OWNER).unwrap();
+        assert!(context.is_paused());
+
+        context.unpause(OWNER).unwrap();
+        assert!(!context.is_paused());
+    }
+
+    #[test]
+    fn others_cannot_pause_or_unpause() {
+        let stranger = Address::new([1; Address::LEN]);
+        let mut context = Context::with_actor(stranger);
+
+        assert_eq!(context.pause(OWNER), Err(NOT_OWNER.into()));
+        assert!(!context.is_paused());
+
+        context.set_actor(OWNER);
+        context.pause(OWNER).unwrap();
+        context.set_actor(stranger);
+        assert_eq!(context.unpause(OWNER), Err(NOT_OWNER.into()));
+        assert!(context.is_paused());
+    }
+
+    #[test]
+    fn rejected_pause_leaves_contract_unpaused() {
+        let mut context = Context::with_actor(Address::new([1; Address::LEN]));
+
+        assert_eq!(context.pause(OWNER), Err(NOT_OWNER.into()));
+
+        let flag: Option<bool> = context.state_cache.get_with_raw_key(PAUSED_KEY).unwrap();
+        assert_eq!(flag, None);
+        assert!(!context.is_paused());
+    }
+
+    #[test]
+    fn key_ends_in_max_chunks() {
+        assert!(PAUSED_KEY.ends_with(&STATE_MAX_CHUNKS));
+    }
+}
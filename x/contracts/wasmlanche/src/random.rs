@@ -0,0 +1,394 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Deterministic shuffling and sampling for contracts.
+//!
+//! [`DeterministicRng`] is the `ChaCha20` keystream of a 32-byte seed, so every validator
+//! running the same action draws the same values. Ranges are sampled by rejection rather than
+//! by taking a remainder, which would favour the low end of any range that does not divide
+//! 2<sup>64</sup>.
+//!
+//! The seed is not secret: [`Context::rng`](crate::Context::rng) seeds it with the action id,
+//! which whoever submits the action knows in advance. Draws that the submitter could profit from
+//! predicting need a seed they cannot influence.
+//!
+//! ## Example
+//! ```
+//! use wasmlanche::random::DeterministicRng;
+//!
+//! let entrants = ["alice", "bob", "carol", "dave", "erin"];
+//! let mut rng = DeterministicRng::new([7; 32]);
+//!
+//! let winners: Vec<_> = rng
+//!     .pick_n_distinct(2, 0..entrants.len() as u64)
+//!     .into_iter()
+//!     .map(|i| entrants[i as usize])
+//!     .collect();
+//!
+//! // the same seed always picks the same winners
+//! let mut again = DeterministicRng::new([7; 32]);
+//! let indexes = again.pick_n_distinct(2, 0..entrants.len() as u64);
+//! assert_eq!(winners, indexes.iter().map(|&i| entrants[i as usize]).collect::<Vec<_>>());
+//! ```
+
+extern crate alloc;
+
+use alloc::{collections::BTreeSet, vec::Vec};
+use core::ops::Range;
+
+/// "expand 32-byte k", the `ChaCha` constant for 256-bit keys.
+const CONSTANTS: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+const BLOCK_WORDS: usize = 16;
+const DOUBLE_ROUNDS: usize = 10;
+
+/// A deterministic generator of uniformly distributed values, seeded with 32 bytes.
+#[derive(Clone, Debug)]
+pub struct DeterministicRng {
+    key: [u32; 8],
+    /// The number of the next block of the keystream.
+    counter: u64,
+    block: [u32; BLOCK_WORDS],
+    /// The next unused word of `block`.
+    index: usize,
+}
+
+impl DeterministicRng {
+    #[must_use]
+    pub fn new(seed: [u8; 32]) -> Self {
+        let mut key = [0; 8];
+        for (word, bytes) in key.iter_mut().zip(seed.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        Self {
+            key,
+            counter: 0,
+            block: [0; BLOCK_WORDS],
+            index: BLOCK_WORDS,
+        }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        if self.index == BLOCK_WORDS {
+            self.block = chacha20_block(&self.key, self.counter);
+            self.counter = self.counter.wrapping_add(1);
+            self.index = 0;
+        }
+
+        let word = self.block[self.index];
+        self.index += 1;
+        word
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let low = u64::from(self.next_u32());
+        let high = u64::from(self.next_u32());
+        (high << 32) | low
+    }
+
+    /// Returns a value in `0..bound`, each equally likely.
+    /// # Panics
+    /// Panics if `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "cannot sample below zero");
+
+        // 2^64 % bound, the values under it would make the low remainders more likely
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let value = self.next_u64();
+            if value >= threshold {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Returns a value in `range`, each equally likely.
+    /// # Panics
+    /// Panics if `range` is empty.
+    pub fn in_range(&mut self, range: Range<u64>) -> u64 {
+        assert!(!range.is_empty(), "cannot sample from an empty range");
+        range.start + self.below(range.end - range.start)
+    }
+
+    /// Puts `items` in a random order, each order equally likely.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        // Fisher-Yates, swapping each item with one at or before it
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1);
+            #[allow(clippy::cast_possible_truncation)] // j <= i
+            items.swap(i, j as usize);
+        }
+    }
+
+    /// Picks one of `items` with a probability proportional to its weight,
+    /// or returns `None` if there are none or they all weigh zero.
+    /// # Panics
+    /// Panics if the weights add up to more than [`u64::MAX`].
+    pub fn sample_weighted<'a, T>(&mut self, items: &'a [(T, u64)]) -> Option<&'a T> {
+        let total = items
+            .iter()
+            .try_fold(0u64, |total, (_, weight)| total.checked_add(*weight));
+        let total = total.expect("the weights add up to more than u64::MAX");
+        if total == 0 {
+            return None;
+        }
+
+        let mut target = self.below(total);
+        items.iter().find_map(|(item, weight)| {
+            if target < *weight {
+                Some(item)
+            } else {
+                target -= weight;
+                None
+            }
+        })
+    }
+
+    /// Picks `n` different values from `range`, each set of values equally likely,
+    /// in the order they were picked.
+    /// # Panics
+    /// Panics if `range` has fewer than `n` values.
+    pub fn pick_n_distinct(&mut self, n: usize, range: Range<u64>) -> Vec<u64> {
+        let len = range.end.saturating_sub(range.start);
+        assert!(
+            n as u64 <= len,
+            "cannot pick {n} distinct values from a range of {len}"
+        );
+
+        // Floyd's algorithm, which draws once per value however many of the range are taken
+        let mut picked = BTreeSet::new();
+        let mut order = Vec::with_capacity(n);
+        for upper in len - n as u64..len {
+            let candidate = self.below(upper + 1);
+            let value = if picked.insert(candidate) {
+                candidate
+            } else {
+                picked.insert(upper);
+                upper
+            };
+            order.push(range.start + value);
+        }
+        order
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl crate::Context {
+    /// A [`DeterministicRng`] seeded with the action id, so every call in the same action draws
+    /// the same values. See the [module docs](crate::random) for when that seed is not enough.
+    #[must_use]
+    pub fn rng(&self) -> DeterministicRng {
+        DeterministicRng::new(self.action_id())
+    }
+}
+
+fn quarter_round(state: &mut [u32; BLOCK_WORDS], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// The `counter`th block of the `ChaCha20` keystream for `key`, with a 64-bit counter and a zero nonce.
+fn chacha20_block(key: &[u32; 8], counter: u64) -> [u32; BLOCK_WORDS] {
+    let mut input = [0; BLOCK_WORDS];
+    input[..4].copy_from_slice(&CONSTANTS);
+    input[4..12].copy_from_slice(key);
+    #[allow(clippy::cast_possible_truncation)] // split into words
+    {
+        input[12] = counter as u32;
+        input[13] = (counter >> 32) as u32;
+    }
+
+    let mut state = input;
+    for _ in 0..DOUBLE_ROUNDS {
+        quarter_round(&mut state, 0, 4, 8, 12);
+        quarter_round(&mut state, 1, 5, 9, 13);
+        quarter_round(&mut state, 2, 6, 10, 14);
+        quarter_round(&mut state, 3, 7, 11, 15);
+        quarter_round(&mut state, 0, 5, 10, 15);
+        quarter_round(&mut state, 1, 6, 11, 12);
+        quarter_round(&mut state, 2, 7, 8, 13);
+        quarter_round(&mut state, 3, 4, 9, 14);
+    }
+
+    for (word, input) in state.iter_mut().zip(input) {
+        *word = word.wrapping_add(input);
+    }
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEEDS: u8 = 200;
+
+    fn seeded(seed: u8) -> DeterministicRng {
+        let mut bytes = [0; 32];
+        bytes[0] = seed;
+        DeterministicRng::new(bytes)
+    }
+
+    /// Pearson's statistic for `observed` counts against equally likely outcomes.
+    fn chi_squared(observed: &[u64]) -> f64 {
+        let total: u64 = observed.iter().sum();
+        let expected = total as f64 / observed.len() as f64;
+        observed
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum()
+    }
+
+    #[test]
+    fn matches_chacha20_keystream() {
+        // RFC 7539 appendix A.1, test vectors 1 and 2: the all-zero key and nonce
+        let mut rng = DeterministicRng::new([0; 32]);
+        let first: Vec<u32> = (0..BLOCK_WORDS).map(|_| rng.next_u32()).collect();
+        let bytes: Vec<u8> = first.iter().flat_map(|word| word.to_le_bytes()).collect();
+        assert_eq!(
+            bytes,
+            [
+                0x76, 0xb8, 0xe0, 0xad, 0xa0, 0xf1, 0x3d, 0x90, 0x40, 0x5d, 0x6a, 0xe5, 0x53, 0x86,
+                0xbd, 0x28, 0xbd, 0xd2, 0x19, 0xb8, 0xa0, 0x8d, 0xed, 0x1a, 0xa8, 0x36, 0xef, 0xcc,
+                0x8b, 0x77, 0x0d, 0xc7, 0xda, 0x41, 0x59, 0x7c, 0x51, 0x57, 0x48, 0x8d, 0x77, 0x24,
+                0xe0, 0x3f, 0xb8, 0xd8, 0x4a, 0x37, 0x6a, 0x43, 0xb8, 0xf4, 0x15, 0x18, 0xa1, 0x1c,
+                0xc3, 0x87, 0xb6, 0x69, 0xb2, 0xee, 0x65, 0x86,
+            ]
+        );
+
+        let second = rng.next_u64().to_le_bytes();
+        assert_eq!(second, [0x9f, 0x07, 0xe7, 0xbe, 0x55, 0x51, 0x38, 0x7a]);
+    }
+
+    #[test]
+    fn fixed_seed_known_answers() {
+        let mut rng = DeterministicRng::new([0; 32]);
+        assert_eq!(rng.next_u64(), 0x903d_f1a0_ade0_b876);
+
+        let mut items = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        seeded(1).shuffle(&mut items);
+        let mut again = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+        seeded(1).shuffle(&mut again);
+        assert_eq!(items, again);
+        assert_ne!(items, [0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        assert_eq!(
+            seeded(1).pick_n_distinct(3, 0..10),
+            seeded(1).pick_n_distinct(3, 0..10)
+        );
+    }
+
+    #[test]
+    fn below_is_uniform() {
+        // a bound just over 2^63 makes half of all u64s remainders of the lowest values,
+        // so taking a remainder without rejecting would put almost every sample in the low half
+        let bound = (1 << 63) + (1 << 62);
+        let mut halves = [0; 2];
+        for seed in 0..SEEDS {
+            let mut rng = seeded(seed);
+            for _ in 0..50 {
+                halves[usize::from(rng.below(bound) >= bound / 2)] += 1;
+            }
+        }
+        // 1 degree of freedom, p = 0.001
+        assert!(chi_squared(&halves) < 10.83, "{halves:?}");
+
+        let mut counts = [0; 6];
+        for seed in 0..SEEDS {
+            let mut rng = seeded(seed);
+            for _ in 0..60 {
+                counts[rng.below(6) as usize] += 1;
+            }
+        }
+        // 5 degrees of freedom, p = 0.001
+        assert!(chi_squared(&counts) < 20.52, "{counts:?}");
+    }
+
+    #[test]
+    fn shuffle_is_uniform() {
+        // every one of the 24 orders of 4 items
+        let mut counts = [0; 24];
+        for seed in 0..SEEDS {
+            let mut rng = seeded(seed);
+            for _ in 0..60 {
+                let mut items = [0, 1, 2, 3];
+                rng.shuffle(&mut items);
+                counts[permutation_index(&items)] += 1;
+            }
+        }
+        // 23 degrees of freedom, p = 0.001
+        assert!(chi_squared(&counts) < 49.73, "{counts:?}");
+    }
+
+    /// The position of `items` among the permutations of `0..items.len()` in lexicographic order.
+    fn permutation_index(items: &[usize]) -> usize {
+        let mut index = 0;
+        for (i, item) in items.iter().enumerate() {
+            let smaller_after = items[i + 1..].iter().filter(|&other| other < item).count();
+            index = index * (items.len() - i) + smaller_after;
+        }
+        index
+    }
+
+    #[test]
+    fn weighted_follows_weights() {
+        let items = [('a', 1), ('b', 0), ('c', 3)];
+        let mut counts = [0; 2];
+        for seed in 0..SEEDS {
+            let mut rng = seeded(seed);
+            for _ in 0..40 {
+                match rng.sample_weighted(&items) {
+                    Some('a') => counts[0] += 1,
+                    Some('c') => counts[1] += 1,
+                    other => panic!("picked {other:?}"),
+                }
+            }
+        }
+        // 'c' weighs three times as much as 'a'
+        let total = counts[0] + counts[1];
+        let expected = [total as f64 / 4.0, total as f64 * 3.0 / 4.0];
+        let statistic: f64 = counts
+            .iter()
+            .zip(expected)
+            .map(|(&count, expected)| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        // 1 degree of freedom, p = 0.001
+        assert!(statistic < 10.83, "{counts:?}");
+
+        assert_eq!(seeded(0).sample_weighted::<char>(&[]), None);
+        assert_eq!(seeded(0).sample_weighted(&[('a', 0)]), None);
+    }
+
+    #[test]
+    fn picks_distinct_values_uniformly() {
+        let mut counts = [0; 10];
+        for seed in 0..SEEDS {
+            let mut rng = seeded(seed);
+            for _ in 0..20 {
+                let picked = rng.pick_n_distinct(3, 100..110);
+                let distinct: BTreeSet<_> = picked.iter().collect();
+                assert_eq!(distinct.len(), 3);
+                for value in picked {
+                    counts[(value - 100) as usize] += 1;
+                }
+            }
+        }
+        // 9 degrees of freedom, p = 0.001
+        assert!(chi_squared(&counts) < 27.88, "{counts:?}");
+
+        let mut all = seeded(0).pick_n_distinct(10, 0..10);
+        all.sort_unstable();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic = "cannot pick 4 distinct values from a range of 3"]
+    fn cannot_pick_more_than_the_range() {
+        seeded(0).pick_n_distinct(4, 0..3);
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Structured revert reasons for failed contract calls.
+//!
+//! Public functions can return `Result<T, Revert>` and check their inputs with
+//! [`ensure!`](crate::ensure) and [`ensure_eq!`](crate::ensure_eq).
+//! The [`Revert`] is serialized as the call's result, so callers and tests can match
+//! on its [`code`](Revert::code) rather than on a panic message.
+//!
+//! ## State is not rolled back
+//! A [`Revert`] is an ordinary result: the call still succeeds, and every state change made
+//! before it was returned is committed. Check everything before writing to state, or panic
+//! when a call has to be undone. The host discards every write of a call that panics or runs
+//! out of gas, including writes that were already flushed from the cache, for instance by
+//! [`Context::call_contract`](crate::Context::call_contract).
+//!
+//! ## Example
+//! ```
+//! use wasmlanche::{ensure, ensure_eq, Address, Revert};
+//!
+//! /// The error codes of this contract.
+//! mod errors {
+//!     use wasmlanche::ErrorCode;
+//!
+//!     pub const NOT_OWNER: ErrorCode = ErrorCode::new(1, "caller is not the owner");
+//!     pub const ZERO_AMOUNT: ErrorCode = ErrorCode::new(2, "amount must not be zero");
+//!     pub const WRONG_NONCE: ErrorCode = ErrorCode::new(3, "unexpected nonce");
+//! }
+//!
+//! fn mint(owner: Address, caller: Address, amount: u64, nonce: u64, expected: u64) -> Result<u64, Revert> {
+//!     ensure!(caller == owner, errors::NOT_OWNER);
+//!     ensure!(amount > 0, errors::ZERO_AMOUNT, "cannot mint {amount} tokens");
+//!     ensure_eq!(nonce, expected, errors::WRONG_NONCE);
+//!     Ok(amount)
+//! }
+//!
+//! let owner = Address::new([1; Address::LEN]);
+//! let err = mint(owner, Address::default(), 5, 0, 0).unwrap_err();
+//! assert_eq!(err.code(), errors::NOT_OWNER.code());
+//!
+//! let err = mint(owner, owner, 0, 0, 0).unwrap_err();
+//! assert_eq!(err.message(), "cannot mint 0 tokens");
+//!
+//! let err = mint(owner, owner, 5, 1, 0).unwrap_err();
+//! assert_eq!(err.message(), "unexpected nonce: 1 != 0");
+//! ```
+
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use borsh::{BorshDeserialize, BorshSerialize};
+use core::fmt;
+
+#[doc(hidden)]
+pub use alloc::format as __format;
+
+/// An error code defined by a contract, with the reason used when a revert gives no message.
+/// Contracts usually declare theirs as consts in one place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorCode {
+    code: u32,
+    reason: &'static str,
+}
+
+impl ErrorCode {
+    #[must_use]
+    pub const fn new(code: u32, reason: &'static str) -> Self {
+        Self { code, reason }
+    }
+
+    #[must_use]
+    pub const fn code(&self) -> u32 {
+        self.code
+    }
+
+    #[must_use]
+    pub const fn reason(&self) -> &'static str {
+        self.reason
+    }
+}
+
+/// The reason a contract call failed, returned as the error of a public function.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Revert {
+    code: u32,
+    message: String,
+}
+
+impl Revert {
+    pub fn new(code: u32, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn code(&self) -> u32 {
+        self.code
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<ErrorCode> for Revert {
+    fn from(code: ErrorCode) -> Self {
+        Self::new(code.code, code.reason.to_string())
+    }
+}
+
+impl fmt::Display for Revert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "reverted with code {}: {}", self.code, self.message)
+    }
+}
+
+/// Returns early with a [`Revert`] for the [`ErrorCode`] if the condition is false.
+///
+/// The revert's message is the code's reason, or is formatted from the remaining arguments.
+/// The enclosing function may return any error type that implements `From<Revert>`.
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr, $code:expr $(,)?) => {
+        if !$cond {
+            return ::core::result::Result::Err($crate::Revert::from($code).into());
+        }
+    };
+    ($cond:expr, $code:expr, $($arg:tt)+) => {
+        if !$cond {
+            let code: $crate::ErrorCode = $code;
+            return ::core::result::Result::Err(
+                $crate::Revert::new(code.code(), $crate::revert::__format!($($arg)+)).into(),
+            );
+        }
+    };
+}
+
+/// Returns early with a [`Revert`] for the [`ErrorCode`] if the two values are not equal.
+///
+/// The revert's message is the code's reason followed by both values, or is formatted from the remaining arguments.
+/// The enclosing function may return any error type that implements `From<Revert>`.
+#[macro_export]
+macro_rules! ensure_eq {
+    ($left:expr, $right:expr, $code:expr $(,)?) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if left != right {
+                    let code: $crate::ErrorCode = $code;
+                    let message =
+                        $crate::revert::__format!("{}: {:?} != {:?}", code.reason(), left, right);
+                    return ::core::result::Result::Err(
+                        $crate::Revert::new(code.code(), message).into(),
+                    );
+                }
+            }
+        }
+    };
+    ($left:expr, $right:expr, $code:expr, $($arg:tt)+) => {
+        $crate::ensure!($left == $right, $code, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ensure, ensure_eq};
+
+    const NOT_ADMIN: ErrorCode = ErrorCode::new(1, "caller is not the admin");
+    const TOO_LARGE: ErrorCode = ErrorCode::new(2, "amount too large");
+    const MISMATCH: ErrorCode = ErrorCode::new(3, "lengths differ");
+
+    fn withdraw(admin: bool, amount: u64) -> Result<u64, Revert> {
+        ensure!(admin, NOT_ADMIN);
+        ensure!(
+            amount <= 100,
+            TOO_LARGE,
+            "cannot withdraw {amount}, the limit is 100"
+        );
+        Ok(amount)
+    }
+
+    #[test]
+    fn ensure_passes() {
+        assert_eq!(withdraw(true, 100), Ok(100));
+    }
+
+    #[test]
+    fn ensure_reverts_with_reason() {
+        let err = withdraw(false, 1).unwrap_err();
+        assert_eq!(err.code(), NOT_ADMIN.code());
+        assert_eq!(err.message(), NOT_ADMIN.reason());
+    }
+
+    #[test]
+    fn ensure_reverts_with_message() {
+        let err = withdraw(true, 101).unwrap_err();
+        assert_eq!(err.code(), TOO_LARGE.code());
+        assert_eq!(err.message(), "cannot withdraw 101, the limit is 100");
+        assert_eq!(
+            err.to_string(),
+            "reverted with code 2: cannot withdraw 101, the limit is 100"
+        );
+    }
+
+    #[test]
+    fn ensure_eq_reports_both_values() {
+        fn check(left: &[u8], right: &[u8]) -> Result<(), Revert> {
+            ensure_eq!(left.len(), right.len(), MISMATCH);
+            ensure_eq!(left, right, MISMATCH, "contents differ");
+            Ok(())
+        }
+
+        assert_eq!(check(&[1], &[1]), Ok(()));
+        assert_eq!(
+            check(&[1], &[1, 2]),
+            Err(Revert::new(3, "lengths differ: 1 != 2"))
+        );
+        assert_eq!(check(&[1], &[2]), Err(Revert::new(3, "contents differ")));
+    }
+
+    #[test]
+    fn converts_into_other_errors() {
+        #[derive(Debug, PartialEq)]
+        enum Error {
+            Revert(Revert),
+        }
+
+        impl From<Revert> for Error {
+            fn from(revert: Revert) -> Self {
+                Self::Revert(revert)
+            }
+        }
+
+        fn fails() -> Result<(), Error> {
+            ensure!(false, NOT_ADMIN);
+            Ok(())
+        }
+
+        assert_eq!(fails(), Err(Error::Revert(NOT_ADMIN.into())));
+    }
+
+    #[test]
+    fn revert_round_trips_through_borsh() {
+        let revert = Revert::new(7, "nope");
+        let bytes = borsh::to_vec(&Err::<u64, _>(revert.clone())).unwrap();
+        let decoded: Result<u64, Revert> = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(decoded, Err(revert));
+    }
+}
@@ -7,6 +7,10 @@ use crate::types::Address as WasmlAddress;
 use thiserror::Error;
 use borsh::BorshSerialize;
 
+mod scheme;
+
+pub use scheme::{AddressScheme, HypersdkScheme, LegacyScheme};
+
 #[derive(Debug, Error)]
 pub enum ExternalCallError {
     #[error("Contract execution failed: {0}")]
@@ -17,17 +21,20 @@ pub enum ExternalCallError {
     Io(#[from] std::io::Error),
 }
 
+// both address types share the same 33 byte layout, so conversions are lossless
+const _: () = assert!(WasmlAddress::LEN == simulator::ADDRESS_LEN);
+
 impl From<WasmlAddress> for simulator::Address {
     fn from(addr: WasmlAddress) -> Self {
-        simulator::Address::new(addr.as_bytes().to_vec())
+        let mut bytes = [0u8; WasmlAddress::LEN];
+        bytes.copy_from_slice(addr.as_bytes());
+        simulator::Address::new(bytes)
     }
 }
 
 impl From<simulator::Address> for WasmlAddress {
     fn from(addr: simulator::Address) -> Self {
-        let mut bytes = [0u8; 33];
-        bytes[..32].copy_from_slice(addr.as_bytes());
-        WasmlAddress::new(bytes)
+        WasmlAddress::new(addr.into_bytes())
     }
 }
 
@@ -36,6 +43,7 @@ pub struct Simulator {
     actor: WasmlAddress,
     height: u64,
     timestamp: u64,
+    scheme: Box<dyn AddressScheme>,
 }
 
 impl Default for Simulator {
@@ -46,23 +54,50 @@ impl Default for Simulator {
 
 impl Simulator {
     pub fn new() -> Self {
+        Self::with_scheme(LegacyScheme)
+    }
+
+    /// Creates a simulator that derives contract and account addresses with `scheme`.
+    pub fn with_scheme<S: AddressScheme + 'static>(scheme: S) -> Self {
         Self {
             vm: Arc::new(RwLock::new(BaseSimulator::new())),
             actor: WasmlAddress::new([0u8; 33]),
             height: 0,
             timestamp: 0,
+            scheme: Box::new(scheme),
         }
     }
 
+    /// Derives the address of an account controlled by `pubkey` using the configured scheme.
+    #[must_use]
+    pub fn derive_account(&self, pubkey: &[u8]) -> WasmlAddress {
+        self.scheme.derive_account(pubkey)
+    }
+
     pub fn create_contract(&mut self, wasm_code: Vec<u8>) -> Result<CreateContractResult, ExternalCallError> {
-        // Create a deterministic address from the wasm code
-        let mut address = [0u8; 33];
-        for (i, byte) in wasm_code.iter().enumerate() {
-            address[i % 33] ^= byte;
-        }
-        let contract_addr = WasmlAddress::new(address);
+        self.deploy_contract(wasm_code, &[])
+    }
+
+    /// Deploys `wasm_code` from the current actor, deriving the address from the
+    /// configured [`AddressScheme`] and `account_creation_data`.
+    /// # Errors
+    /// Returns an [`ExternalCallError`] if the contract cannot be created.
+    /// # Panics
+    /// Panics if the underlying simulator lock is poisoned.
+    pub fn deploy_contract(
+        &mut self,
+        wasm_code: Vec<u8>,
+        account_creation_data: &[u8],
+    ) -> Result<CreateContractResult, ExternalCallError> {
+        let contract_addr = self
+            .scheme
+            .derive_contract(&self.actor, &wasm_code, account_creation_data);
 
-        self.vm.write().unwrap().create_contract(contract_addr.clone().into(), wasm_code);
+        self.vm
+            .read()
+            .unwrap()
+            .create_contract(contract_addr.clone().into(), wasm_code)
+            .map_err(|e| ExternalCallError::ContractCreation(e.to_string()))?;
         
         Ok(CreateContractResult {
             address: contract_addr,
@@ -71,7 +106,7 @@ impl Simulator {
 
     pub fn call_contract<U: BorshSerialize>(
         &mut self,
-        _contract: WasmlAddress,
+        contract: WasmlAddress,
         method: &str,
         params: U,
         gas: u64,
@@ -79,7 +114,7 @@ impl Simulator {
         let args = borsh::to_vec(&params)
             .map_err(|e| ExternalCallError::ContractExecution(e.to_string()))?;
         
-        let result = self.vm.write().unwrap().execute_wasm(&[], method, &args, gas)
+        let result = self.vm.read().unwrap().call_contract(contract.into(), method, &args, gas)
             .map_err(|e| ExternalCallError::ContractExecution(e.to_string()))?;
         
         Ok(result)
@@ -90,6 +125,7 @@ impl Simulator {
     }
 
     pub fn set_actor(&mut self, actor: WasmlAddress) {
+        self.vm.read().unwrap().set_actor(actor.clone().into());
         self.actor = actor;
     }
 
@@ -98,6 +134,7 @@ impl Simulator {
     }
 
     pub fn set_height(&mut self, height: u64) {
+        self.vm.read().unwrap().set_height(height);
         self.height = height;
     }
 
@@ -106,6 +143,7 @@ impl Simulator {
     }
 
     pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.vm.read().unwrap().set_timestamp(timestamp);
         self.timestamp = timestamp;
     }
 
@@ -133,6 +171,7 @@ pub struct CreateContractResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{pause::PAUSED_KEY, Revert};
 
     #[test]
     fn initial_balance_is_zero() {
@@ -158,4 +197,126 @@ mod tests {
         sim.set_balance(addr.clone(), balance);
         assert_eq!(sim.get_balance(addr), balance);
     }
+
+    #[test]
+    fn deploy_contract_uses_scheme() {
+        let mut sim = Simulator::with_scheme(HypersdkScheme);
+        let code: Vec<u8> = (0..16).collect();
+
+        let address = sim.deploy_contract(code.clone(), b"salt").unwrap().address;
+        // the hypersdk's `codec.CreateAddress(0, sha256(sha256(code) || "salt"))`
+        let expected: simulator::Address =
+            "00d3791ae1c6623590072873b1bb75b9e1ab806bb636461bb492390d35a4a89b92".parse().unwrap();
+        assert_eq!(address.as_bytes(), expected.as_bytes());
+
+        let vm = sim.vm.read().unwrap();
+        let state = vm.get_state();
+        assert_eq!(state.read().unwrap().get_contract(&expected), Some(&code));
+    }
+
+    /// A contract whose `revert` writes `key!` and returns `result`, and whose `panic` writes `key!` and traps.
+    fn write_then(result: &[u8]) -> Vec<u8> {
+        let data: String = result.iter().map(|byte| format!("\\{byte:02x}")).collect();
+        format!(
+            r#"
+            (module
+                (import "contract" "set_call_result" (func $set_call_result (param i32 i32)))
+                (import "state" "put" (func $put (param i32 i32)))
+                (memory (export "memory") 1)
+                ;; borsh `vec![(b"key!", b"abc")]`
+                (data (i32.const 0) "\01\00\00\00\04\00\00\00key!\03\00\00\00abc")
+                (data (i32.const 32) "{data}")
+                (func (export "revert") (param i32)
+                    (call $put (i32.const 0) (i32.const 19))
+                    (call $set_call_result (i32.const 32) (i32.const {len})))
+                (func (export "panic") (param i32)
+                    (call $put (i32.const 0) (i32.const 19))
+                    unreachable))
+            "#,
+            len = result.len(),
+        )
+        .into_bytes()
+    }
+
+    fn stored(sim: &Simulator, contract: WasmlAddress) -> Option<Vec<u8>> {
+        let vm = sim.vm.read().unwrap();
+        let state = vm.get_state();
+        let value = state.read().unwrap().get_contract_value(&contract.into(), b"key!").cloned();
+        value
+    }
+
+    #[test]
+    fn reverts_keep_writes_and_panics_discard_them() {
+        let revert = Revert::new(2, "cannot mint 0 tokens");
+        let code = write_then(&borsh::to_vec(&Err::<u64, _>(&revert)).unwrap());
+
+        let mut sim = Simulator::new();
+        let contract = sim.create_contract(code.clone()).unwrap().address;
+        let result = sim.call_contract(contract.clone(), "revert", (), 1_000_000).unwrap();
+
+        let result: Result<u64, Revert> = borsh::from_slice(&result).unwrap();
+        assert_eq!(result, Err(revert));
+        assert_eq!(stored(&sim, contract), Some(b"abc".to_vec()));
+
+        let mut sim = Simulator::new();
+        let contract = sim.create_contract(code).unwrap().address;
+        sim.call_contract(contract.clone(), "panic", (), 1_000_000)
+            .unwrap_err();
+
+        assert_eq!(stored(&sim, contract), None);
+    }
+
+    /// A contract whose `pause` stores the pause flag the way [`Context::pause`](crate::Context::pause)
+    /// flushes it, and whose `pause_then_panic` stores it and traps.
+    fn pause_flag() -> Vec<u8> {
+        let put = borsh::to_vec(&vec![(PAUSED_KEY.to_vec(), vec![u8::from(true)])]).unwrap();
+        let data: String = put.iter().map(|byte| format!("\\{byte:02x}")).collect();
+        format!(
+            r#"
+            (module
+                (import "state" "put" (func $put (param i32 i32)))
+                (memory (export "memory") 1)
+                (data (i32.const 0) "{data}")
+                (func (export "pause") (param i32)
+                    (call $put (i32.const 0) (i32.const {len})))
+                (func (export "pause_then_panic") (param i32)
+                    (call $put (i32.const 0) (i32.const {len}))
+                    unreachable))
+            "#,
+            len = put.len(),
+        )
+        .into_bytes()
+    }
+
+    fn is_paused(sim: &Simulator, contract: WasmlAddress) -> bool {
+        let vm = sim.vm.read().unwrap();
+        let state = vm.get_state();
+        let flag = state.read().unwrap().get_contract_value(&contract.into(), PAUSED_KEY).cloned();
+        flag.is_some_and(|flag| borsh::from_slice(&flag).unwrap())
+    }
+
+    #[test]
+    fn failed_pauses_leave_the_contract_unpaused() {
+        let mut sim = Simulator::new();
+        let contract = sim.create_contract(pause_flag()).unwrap().address;
+
+        sim.call_contract(contract.clone(), "pause_then_panic", (), 1_000_000)
+            .unwrap_err();
+        assert!(!is_paused(&sim, contract.clone()));
+
+        sim.call_contract(contract.clone(), "pause", (), 1_000_000).unwrap();
+        assert!(is_paused(&sim, contract));
+    }
+
+    #[test]
+    fn address_conversions_round_trip() {
+        let bytes: [u8; 33] = core::array::from_fn(|i| i as u8);
+        let address = WasmlAddress::new(bytes);
+
+        let base: simulator::Address = address.into();
+        assert_eq!(base.as_bytes(), &bytes);
+
+        let back: WasmlAddress = base.into();
+        assert_eq!(back.as_bytes(), &bytes);
+    }
 }
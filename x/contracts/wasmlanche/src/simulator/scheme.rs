@@ -0,0 +1,127 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+use crate::types::Address as WasmlAddress;
+use sha2::{Digest, Sha256};
+use simulator::{hypersdk_contract_address, HYPERSDK_TYPE_ID};
+
+/// Rules used by the [`Simulator`](super::Simulator) to derive addresses.
+pub trait AddressScheme: Send + Sync {
+    /// Derives the address of a contract account deployed from `code`.
+    /// `salt` is the account-creation data passed alongside the deploy.
+    fn derive_contract(&self, deployer: &WasmlAddress, code: &[u8], salt: &[u8]) -> WasmlAddress;
+
+    /// Derives the address of an account controlled by `pubkey`.
+    fn derive_account(&self, pubkey: &[u8]) -> WasmlAddress;
+}
+
+/// Matches the Go hypersdk derivation byte-for-byte.
+///
+/// * contract id: `sha256(code)`
+/// * contract account: `[0] || sha256(contract_id || salt)`
+/// * ed25519 account: `[0] || sha256(pubkey)`
+///
+/// The deployer does not take part in the derivation on-chain,
+/// so two deployers publishing the same code and salt collide.
+/// Contract addresses come from [`simulator::hypersdk_contract_address`], which
+/// [`simulator::Simulator::deploy`] uses as well.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HypersdkScheme;
+
+impl AddressScheme for HypersdkScheme {
+    fn derive_contract(&self, _deployer: &WasmlAddress, code: &[u8], salt: &[u8]) -> WasmlAddress {
+        hypersdk_contract_address(code, salt).into()
+    }
+
+    fn derive_account(&self, pubkey: &[u8]) -> WasmlAddress {
+        typed_address(HYPERSDK_TYPE_ID, &Sha256::digest(pubkey).into())
+    }
+}
+
+/// The original simulator behavior: contract addresses are the code folded
+/// over itself with xor, and account addresses are the raw key bytes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LegacyScheme;
+
+impl AddressScheme for LegacyScheme {
+    fn derive_contract(&self, _deployer: &WasmlAddress, code: &[u8], _salt: &[u8]) -> WasmlAddress {
+        let mut address = [0u8; WasmlAddress::LEN];
+        for (i, byte) in code.iter().enumerate() {
+            address[i % WasmlAddress::LEN] ^= byte;
+        }
+        WasmlAddress::new(address)
+    }
+
+    fn derive_account(&self, pubkey: &[u8]) -> WasmlAddress {
+        let mut address = [0u8; WasmlAddress::LEN];
+        let len = pubkey.len().min(WasmlAddress::LEN);
+        address[..len].copy_from_slice(&pubkey[..len]);
+        WasmlAddress::new(address)
+    }
+}
+
+fn typed_address(type_id: u8, id: &[u8; 32]) -> WasmlAddress {
+    let mut address = [0u8; WasmlAddress::LEN];
+    address[0] = type_id;
+    address[1..].copy_from_slice(id);
+    WasmlAddress::new(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // computed with `codec.CreateAddress(0, sha256(sha256(code) || salt))`
+    // and `auth.NewED25519Address(pk)` for `code = 0x00..=0x0f`, `pk = [7; 32]`
+    const CONTRACT_NO_SALT: &str =
+        "00fc793c641b354b10b9a264ad4f541f6efe8445a0d05fe39336a126252b166e8b";
+    const CONTRACT_SALT: &str =
+        "00d3791ae1c6623590072873b1bb75b9e1ab806bb636461bb492390d35a4a89b92";
+    const ED25519_ACCOUNT: &str =
+        "004bb06f8e4e3a7715d201d573d0aa423762e55dabd61a2c02278fa56cc6d294e0";
+
+    fn code() -> Vec<u8> {
+        (0..16).collect()
+    }
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn hypersdk_contract_known_answers() {
+        let deployer = WasmlAddress::new([9; 33]);
+
+        let address = HypersdkScheme.derive_contract(&deployer, &code(), &[]);
+        assert_eq!(address.as_bytes(), from_hex(CONTRACT_NO_SALT));
+
+        let address = HypersdkScheme.derive_contract(&deployer, &code(), b"salt");
+        assert_eq!(address.as_bytes(), from_hex(CONTRACT_SALT));
+    }
+
+    #[test]
+    fn hypersdk_contract_ignores_deployer() {
+        let a = HypersdkScheme.derive_contract(&WasmlAddress::new([1; 33]), &code(), &[]);
+        let b = HypersdkScheme.derive_contract(&WasmlAddress::new([2; 33]), &code(), &[]);
+        assert_eq!(a.as_bytes(), b.as_bytes());
+    }
+
+    #[test]
+    fn hypersdk_account_known_answer() {
+        let address = HypersdkScheme.derive_account(&[7; 32]);
+        assert_eq!(address.as_bytes(), from_hex(ED25519_ACCOUNT));
+    }
+
+    #[test]
+    fn legacy_contract_folds_code() {
+        let code = [1u8; 34];
+        let address = LegacyScheme.derive_contract(&WasmlAddress::ZERO, &code, &[]);
+
+        let mut expected = [1u8; 33];
+        expected[0] = 0;
+        assert_eq!(address.as_bytes(), expected);
+    }
+}
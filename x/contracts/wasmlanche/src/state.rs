@@ -184,6 +184,13 @@ impl Cache {
         self.store(((key, value),))
     }
 
+    /// Stores `value` under a key that is not part of a [`Schema`], such as the SDK's own flags.
+    pub(crate) fn store_raw(&mut self, key: &[u8], value: Vec<u8>) {
+        self.change_count += 1;
+        self.byte_count += size_of::<u32>() + key.len() + size_of::<u32>() + value.len();
+        self.cache.insert(CacheKey::from(key), Query::Changed(value));
+    }
+
     pub(crate) fn get_with_raw_key<V>(&mut self, key: &[u8]) -> Result<Option<V>, Error>
     where
         V: BorshDeserialize,
@@ -194,6 +201,8 @@ impl Cache {
             value
                 .to_option()
                 .map(Deref::deref)
+                // deleted keys are empty until the cache is flushed
+                .filter(|bytes| !bytes.is_empty())
                 .map(from_slice)
                 .transpose()
                 .map_err(|_| Error::Deserialization)
@@ -0,0 +1,411 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Deterministic time arithmetic for contracts.
+//!
+//! [`Timestamp`]s and [`Duration`]s are whole seconds, every operation is checked,
+//! and nothing depends on floats, locales, or the host clock.
+//!
+//! ## Example
+//! ```
+//! use wasmlanche::time::{Duration, Error, Timestamp};
+//!
+//! /// Amount of `total` vested linearly over `period` after a one year cliff from `start`.
+//! fn vested(total: u64, start: Timestamp, period: Duration, now: Timestamp) -> Result<u64, Error> {
+//!     let cliff = start.checked_add(Duration::days(365)?)?;
+//!     if now.is_before(cliff) {
+//!         return Ok(0);
+//!     }
+//!
+//!     let elapsed = now.elapsed_since(start)?.min(period);
+//!     let vested = u128::from(total) * u128::from(elapsed.as_secs()) / u128::from(period.as_secs());
+//!     u64::try_from(vested).map_err(|_| Error::Overflow)
+//! }
+//!
+//! let start = Timestamp::from_secs(1_700_000_000);
+//! let period = Duration::days(4 * 365)?;
+//!
+//! assert_eq!(vested(1000, start, period, start.checked_add(Duration::days(364)?)?)?, 0);
+//! assert_eq!(vested(1000, start, period, start.checked_add(Duration::days(730)?)?)?, 500);
+//! assert_eq!(vested(1000, start, period, start.checked_add(Duration::days(5000)?)?)?, 1000);
+//! # Ok::<(), Error>(())
+//! ```
+
+extern crate alloc;
+
+use alloc::{format, string::String};
+use borsh::{BorshDeserialize, BorshSerialize};
+use displaydoc::Display;
+
+const SECS_PER_MINUTE: u64 = 60;
+const SECS_PER_HOUR: u64 = 60 * SECS_PER_MINUTE;
+const SECS_PER_DAY: u64 = 24 * SECS_PER_HOUR;
+
+/// Last second representable in RFC 3339, which only allows four digit years.
+const MAX_RFC3339_SECS: u64 = 253_402_300_799;
+
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum Error {
+    /// the result does not fit in a `u64` of seconds
+    Overflow,
+    /// the result would be negative
+    Underflow,
+    /// the timestamp is after 9999-12-31T23:59:59Z
+    OutOfRange,
+}
+
+/// A length of time in whole seconds.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct Duration(u64);
+
+impl Duration {
+    pub const ZERO: Self = Self(0);
+
+    #[must_use]
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(secs)
+    }
+
+    /// # Errors
+    /// Returns [`Error::Overflow`] if the duration does not fit in a `u64` of seconds.
+    pub fn minutes(minutes: u64) -> Result<Self, Error> {
+        Self::from_units(minutes, SECS_PER_MINUTE)
+    }
+
+    /// # Errors
+    /// Returns [`Error::Overflow`] if the duration does not fit in a `u64` of seconds.
+    pub fn hours(hours: u64) -> Result<Self, Error> {
+        Self::from_units(hours, SECS_PER_HOUR)
+    }
+
+    /// # Errors
+    /// Returns [`Error::Overflow`] if the duration does not fit in a `u64` of seconds.
+    pub fn days(days: u64) -> Result<Self, Error> {
+        Self::from_units(days, SECS_PER_DAY)
+    }
+
+    fn from_units(count: u64, secs_per_unit: u64) -> Result<Self, Error> {
+        count
+            .checked_mul(secs_per_unit)
+            .map(Self)
+            .ok_or(Error::Overflow)
+    }
+
+    #[must_use]
+    pub const fn as_secs(self) -> u64 {
+        self.0
+    }
+
+    /// # Errors
+    /// Returns [`Error::Overflow`] if the sum does not fit in a `u64` of seconds.
+    pub fn checked_add(self, other: Self) -> Result<Self, Error> {
+        self.0.checked_add(other.0).map(Self).ok_or(Error::Overflow)
+    }
+
+    /// # Errors
+    /// Returns [`Error::Underflow`] if `other` is longer than `self`.
+    pub fn checked_sub(self, other: Self) -> Result<Self, Error> {
+        self.0
+            .checked_sub(other.0)
+            .map(Self)
+            .ok_or(Error::Underflow)
+    }
+}
+
+/// A point in time in whole seconds since the Unix epoch, in UTC.
+/// [`Context::time`](crate::Context::time) returns the timestamp of the current block.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    /// 1970-01-01T00:00:00Z
+    pub const UNIX_EPOCH: Self = Self(0);
+
+    #[must_use]
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(secs)
+    }
+
+    #[must_use]
+    pub const fn as_secs(self) -> u64 {
+        self.0
+    }
+
+    /// # Errors
+    /// Returns [`Error::Overflow`] if the result does not fit in a `u64` of seconds.
+    pub fn checked_add(self, duration: Duration) -> Result<Self, Error> {
+        self.0
+            .checked_add(duration.0)
+            .map(Self)
+            .ok_or(Error::Overflow)
+    }
+
+    /// # Errors
+    /// Returns [`Error::Underflow`] if the result would be before the Unix epoch.
+    pub fn checked_sub(self, duration: Duration) -> Result<Self, Error> {
+        self.0
+            .checked_sub(duration.0)
+            .map(Self)
+            .ok_or(Error::Underflow)
+    }
+
+    #[must_use]
+    pub fn is_before(self, other: Self) -> bool {
+        self < other
+    }
+
+    #[must_use]
+    pub fn is_after(self, other: Self) -> bool {
+        self > other
+    }
+
+    /// Returns how long after `earlier` this timestamp is.
+    /// # Errors
+    /// Returns [`Error::Underflow`] if `earlier` is after this timestamp.
+    pub fn elapsed_since(self, earlier: Self) -> Result<Duration, Error> {
+        self.0
+            .checked_sub(earlier.0)
+            .map(Duration)
+            .ok_or(Error::Underflow)
+    }
+
+    /// Formats the timestamp as RFC 3339 in UTC, e.g. `2024-02-29T23:59:59Z`.
+    /// # Errors
+    /// Returns [`Error::OutOfRange`] for timestamps after 9999-12-31T23:59:59Z,
+    /// which have more than four digit years.
+    pub fn to_rfc3339_utc(self) -> Result<String, Error> {
+        if self.0 > MAX_RFC3339_SECS {
+            return Err(Error::OutOfRange);
+        }
+
+        let (days, secs) = (self.0 / SECS_PER_DAY, self.0 % SECS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+        let (hour, minute, second) = (
+            secs / SECS_PER_HOUR,
+            secs % SECS_PER_HOUR / SECS_PER_MINUTE,
+            secs % SECS_PER_MINUTE,
+        );
+
+        Ok(format!(
+            "{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"
+        ))
+    }
+}
+
+impl From<u64> for Timestamp {
+    fn from(secs: u64) -> Self {
+        Self(secs)
+    }
+}
+
+impl From<Timestamp> for u64 {
+    fn from(timestamp: Timestamp) -> Self {
+        timestamp.0
+    }
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian `(year, month, day)`.
+///
+/// Counts from 0000-03-01 so that leap days fall at the end of each year,
+/// then splits the days into 400 year eras, which all have the same number of days.
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    const DAYS_PER_ERA: u64 = 146_097;
+    // days from 0000-03-01 to 1970-01-01
+    const EPOCH_OFFSET: u64 = 719_468;
+
+    let days = days + EPOCH_OFFSET;
+    let era = days / DAYS_PER_ERA;
+    let day_of_era = days % DAYS_PER_ERA;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months starting from March
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc3339_known_answers() {
+        let cases = [
+            (0, "1970-01-01T00:00:00Z"),
+            // leap day in a year divisible by 400
+            (951_827_696, "2000-02-29T12:34:56Z"),
+            (951_868_800, "2000-03-01T00:00:00Z"),
+            (1_709_251_199, "2024-02-29T23:59:59Z"),
+            (1_735_689_599, "2024-12-31T23:59:59Z"),
+            // 2100 is divisible by 100 but not 400, so it has no leap day
+            (4_107_542_399, "2100-02-28T23:59:59Z"),
+            (4_107_542_400, "2100-03-01T00:00:00Z"),
+            // a leap day at the end of an era
+            (13_574_563_200, "2400-02-29T00:00:00Z"),
+            (MAX_RFC3339_SECS, "9999-12-31T23:59:59Z"),
+        ];
+
+        for (secs, expected) in cases {
+            assert_eq!(
+                Timestamp::from_secs(secs).to_rfc3339_utc().unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn rfc3339_rejects_five_digit_years() {
+        assert_eq!(
+            Timestamp::from_secs(MAX_RFC3339_SECS + 1).to_rfc3339_utc(),
+            Err(Error::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn every_day_follows_the_previous_one() {
+        let days_in_month = |year: u64, month: u64| match month {
+            2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        };
+
+        let mut previous = civil_from_days(0);
+        assert_eq!(previous, (1970, 1, 1));
+
+        // through the 2000 and 2400 leap years and the non-leap 2100, 2200, and 2300
+        for days in 1..200_000 {
+            let (year, month, day) = previous;
+            let expected = if day < days_in_month(year, month) {
+                (year, month, day + 1)
+            } else if month < 12 {
+                (year, month + 1, 1)
+            } else {
+                (year + 1, 1, 1)
+            };
+
+            let current = civil_from_days(days);
+            assert_eq!(current, expected, "{days} days after the epoch");
+            previous = current;
+        }
+    }
+
+    /// Deterministic pseudo-random `u64`s, seeded with `seed`.
+    fn samples(seed: u64) -> impl Iterator<Item = u64> {
+        core::iter::successors(Some(seed), |x| {
+            Some(
+                x.wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407),
+            )
+        })
+        .take(10_000)
+    }
+
+    #[test]
+    fn add_and_sub_round_trip() {
+        for (a, b) in samples(1).zip(samples(2)) {
+            let (timestamp, duration) = (Timestamp::from_secs(a >> 1), Duration::from_secs(b >> 2));
+
+            let later = timestamp.checked_add(duration).unwrap();
+            assert_eq!(later.checked_sub(duration), Ok(timestamp));
+            assert_eq!(later.elapsed_since(timestamp), Ok(duration));
+            assert!(!later.is_before(timestamp));
+        }
+    }
+
+    #[test]
+    fn arithmetic_is_checked() {
+        let max = Timestamp::from_secs(u64::MAX);
+
+        assert_eq!(
+            max.checked_add(Duration::from_secs(1)),
+            Err(Error::Overflow)
+        );
+        assert_eq!(
+            Timestamp::UNIX_EPOCH.checked_sub(Duration::from_secs(1)),
+            Err(Error::Underflow)
+        );
+        assert_eq!(
+            Timestamp::UNIX_EPOCH.elapsed_since(max),
+            Err(Error::Underflow)
+        );
+        assert_eq!(Duration::days(u64::MAX / 86_400 + 1), Err(Error::Overflow));
+        assert_eq!(
+            Duration::ZERO.checked_sub(Duration::from_secs(1)),
+            Err(Error::Underflow)
+        );
+    }
+
+    #[test]
+    fn unit_constructors() {
+        assert_eq!(Duration::minutes(2).unwrap().as_secs(), 120);
+        assert_eq!(Duration::hours(2).unwrap().as_secs(), 7200);
+        assert_eq!(Duration::days(2).unwrap().as_secs(), 172_800);
+    }
+
+    #[test]
+    fn cliff_vesting() {
+        let start = Timestamp::from_secs(1_700_000_000);
+        let cliff = start.checked_add(Duration::days(90).unwrap()).unwrap();
+        let end = cliff.checked_add(Duration::days(270).unwrap()).unwrap();
+        let period = end.elapsed_since(cliff).unwrap();
+
+        let vested = |now: Timestamp| -> u64 {
+            if now.is_before(cliff) {
+                return 0;
+            }
+            let elapsed = now.elapsed_since(cliff).unwrap().min(period);
+            1_000 * elapsed.as_secs() / period.as_secs()
+        };
+
+        assert_eq!(vested(start), 0);
+        assert_eq!(
+            vested(cliff.checked_sub(Duration::from_secs(1)).unwrap()),
+            0
+        );
+        assert_eq!(vested(cliff), 0);
+        assert_eq!(
+            vested(cliff.checked_add(Duration::days(135).unwrap()).unwrap()),
+            500
+        );
+        assert_eq!(vested(end), 1_000);
+        assert!(end.is_after(cliff));
+        assert_eq!(
+            vested(end.checked_add(Duration::days(1).unwrap()).unwrap()),
+            1_000
+        );
+    }
+}
@@ -3,7 +3,7 @@
 
 extern crate alloc;
 
-use alloc::boxed::Box;
+use alloc::{boxed::Box, vec::Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
 use core::{array, mem::size_of};
@@ -27,7 +27,6 @@ impl From<Box<[u8]>> for ContractId {
 }
 
 /// Represents an address where a smart contract is deployed.
-#[cfg_attr(feature = "debug", derive(Debug))]
 #[derive(Clone, Copy, Ord, PartialOrd, PartialEq, Eq, BorshSerialize, BorshDeserialize, Hash)]
 #[repr(transparent)]
 pub struct Address([u8; 33]);
@@ -51,6 +50,13 @@ impl Address {
     }
 }
 
+#[cfg(feature = "debug")]
+impl core::fmt::Debug for Address {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Address({})", crate::fmt::ShortHex::new(&self.0))
+    }
+}
+
 impl Default for Address {
     fn default() -> Self {
         Self([0; Self::LEN])
@@ -71,3 +77,44 @@ impl AsRef<[u8]> for Address {
         &self.0
     }
 }
+
+/// The borsh-encoded result of a dispatched action.
+/// It is written to the host as-is so callers decode the handler's return type directly.
+#[doc(hidden)]
+pub struct DispatchResult(Vec<u8>);
+
+impl DispatchResult {
+    /// # Panics
+    /// Panics if the value cannot be serialized
+    #[must_use]
+    pub fn new<T: BorshSerialize>(value: &T) -> Self {
+        Self(borsh::to_vec(value).expect("failed to serialize result"))
+    }
+
+    #[must_use]
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl BorshSerialize for DispatchResult {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        writer.write_all(&self.0)
+    }
+}
+
+impl BorshDeserialize for DispatchResult {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let mut bytes = Vec::new();
+        let mut buf = [0u8; 256];
+
+        loop {
+            match reader.read(&mut buf)? {
+                0 => break,
+                n => bytes.extend_from_slice(&buf[..n]),
+            }
+        }
+
+        Ok(Self(bytes))
+    }
+}
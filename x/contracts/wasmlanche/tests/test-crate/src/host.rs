@@ -0,0 +1,58 @@
+// Copyright (C) 2024, Ava Labs, Inc. All rights reserved.
+// See the file LICENSE for licensing terms.
+
+//! Calls the exports generated by `#[public]` the way the host does: the borsh-encoded
+//! context and arguments are copied into memory from the contract's `alloc`, the export is
+//! called with a pointer to them, and the result is whatever it passes to `set_call_result`.
+
+use std::cell::RefCell;
+use wasmlanche::{borsh, Address, HostPtr, ID_LEN};
+
+thread_local! {
+    static CALL_RESULT: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
+}
+
+#[no_mangle]
+unsafe extern "C" fn set_call_result(ptr: *const u8, len: usize) {
+    let result = std::slice::from_raw_parts(ptr, len).to_vec();
+    CALL_RESULT.set(Some(result));
+}
+
+extern "C-unwind" {
+    fn alloc(len: usize) -> HostPtr;
+}
+
+/// An export generated by `#[public]`.
+pub type Export = unsafe extern "C-unwind" fn(HostPtr);
+
+/// Declares the exports named `$name`, callable as `exports::$name`.
+macro_rules! exports {
+    ($($name:ident),* $(,)?) => {
+        mod exports {
+            extern "C-unwind" {
+                $(pub fn $name(args: wasmlanche::HostPtr);)*
+            }
+        }
+    };
+}
+
+pub(crate) use exports;
+
+/// Calls `export` as `actor`, with the already borsh-encoded `args`, and returns the encoded result.
+/// # Panics
+/// Panics if the export does, for instance because it cannot decode `args`.
+pub fn call(export: Export, actor: Address, args: &[u8]) -> Vec<u8> {
+    let context = borsh::to_vec(&(Address::ZERO, actor, 0u64, 0u64, [0u8; ID_LEN]))
+        .expect("failed to serialize the context");
+    let params = [context, args.to_vec()].concat();
+
+    unsafe {
+        let ptr = alloc(params.len());
+        std::ptr::copy(params.as_ptr(), ptr.as_ptr().cast_mut(), params.len());
+        export(ptr);
+    }
+
+    CALL_RESULT
+        .take()
+        .expect("the export did not set a call result")
+}
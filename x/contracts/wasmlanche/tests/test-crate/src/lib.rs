@@ -5,7 +5,10 @@ use std::{
     alloc::{GlobalAlloc, Layout, System},
     cell::UnsafeCell,
 };
-use wasmlanche::{public, Context};
+use wasmlanche::{ensure, public, public_dispatch, when_not_paused, Address, Context, Revert};
+
+#[cfg(test)]
+mod host;
 
 struct HighestAllocatedAddress {
     value: UnsafeCell<usize>,
@@ -64,9 +67,134 @@ pub fn combine_last_bit_of_each_id_byte(context: &mut Context) -> u32 {
         .fold(0, |acc, byte| (acc << 1) + (byte & 1))
 }
 
+#[public]
+#[when_not_paused]
+pub fn guarded(_: &mut Context) -> Result<bool, Revert> {
+    Ok(true)
+}
+
+#[public]
+pub fn emit_transfer(context: &mut Context, amount: u64) {
+    let actor = context.actor();
+    context.emit_event("Transfer", &(actor, amount));
+}
+
+#[public]
+pub fn pick_winners(context: &mut Context, entrants: Vec<Address>) -> Vec<Address> {
+    context
+        .rng()
+        .pick_n_distinct(3, 0..entrants.len() as u64)
+        .into_iter()
+        .map(|i| entrants[i as usize])
+        .collect()
+}
+
+#[public]
+pub fn block_height(context: &mut Context) -> u64 {
+    context.height()
+}
+
+/// The account allowed to [`mint`].
+pub fn minter() -> Address {
+    Address::new([7; Address::LEN])
+}
+
+pub mod errors {
+    use wasmlanche::ErrorCode;
+
+    pub const NOT_MINTER: ErrorCode = ErrorCode::new(1, "only the minter may mint");
+    pub const ZERO_AMOUNT: ErrorCode = ErrorCode::new(2, "amount must not be zero");
+}
+
+#[public]
+pub fn mint(context: &mut Context, amount: u64) -> Result<u64, Revert> {
+    ensure!(context.actor() == minter(), errors::NOT_MINTER);
+    ensure!(
+        amount > 0,
+        errors::ZERO_AMOUNT,
+        "cannot mint {amount} tokens"
+    );
+    Ok(amount)
+}
+
+// `transfer` first took `(to, amount)`, `memo` was added later
+
+#[public]
+pub fn transfer(
+    _: &mut Context,
+    to: Address,
+    amount: u64,
+    memo: Option<String>,
+) -> (u64, Option<String>) {
+    let _ = to;
+    (amount, memo)
+}
+
+#[public]
+pub fn transfer_with_fee(
+    _: &mut Context,
+    to: Address,
+    amount: u64,
+    #[default(10)] fee: u64,
+) -> u64 {
+    let _ = to;
+    amount + fee
+}
+
+#[public(strict_args)]
+pub fn strict_transfer(
+    _: &mut Context,
+    to: Address,
+    amount: u64,
+    memo: Option<String>,
+) -> (u64, Option<String>) {
+    let _ = to;
+    (amount, memo)
+}
+
+#[public_dispatch]
+pub mod actions {
+    use wasmlanche::{
+        borsh::{BorshDeserialize, BorshSerialize},
+        Address, Context,
+    };
+
+    #[derive(BorshSerialize, BorshDeserialize)]
+    #[borsh(crate = "wasmlanche::borsh")]
+    pub enum Action {
+        Deposit { to: Address, amount: u64 },
+        Double(u64),
+    }
+
+    impl Action {
+        fn deposit(context: &mut Context, to: Address, amount: u64) -> u64 {
+            context.get_balance(to) + amount
+        }
+
+        fn double(_: &mut Context, value: u64) -> u64 {
+            value * 2
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use wasmlanche::{Address, Context};
+    use super::{
+        actions::{self, Action},
+        block_height, emit_transfer, errors, guarded, host, minter, pick_winners,
+    };
+    use wasmlanche::{
+        borsh, pause::PAUSED, Address, Bech32Error, BlockInfo, Context, FoldError, FoldOp, Revert,
+    };
+
+    host::exports!(
+        mint,
+        transfer,
+        transfer_with_fee,
+        strict_transfer,
+        execute,
+        double,
+    );
 
     #[test]
     fn test_balance() {
@@ -80,4 +208,219 @@ mod tests {
         let balance = context.get_balance(address);
         assert_eq!(balance, amount);
     }
+
+    #[test]
+    fn test_bech32() {
+        let context = Context::with_actor(Address::default());
+        let encoded = "cosmos1qqqsyqcyq5rqwzqfpg9scrgwpugpzysnrk363e";
+        let data: Vec<u8> = (0..20).collect();
+
+        context.mock_bech32_encode("cosmos", &data, Ok(encoded.to_string()));
+        context.mock_bech32_decode("osmo", encoded, Err(Bech32Error::WrongHrp));
+
+        assert_eq!(
+            context.bech32_encode("cosmos", &data),
+            Ok(encoded.to_string())
+        );
+        assert_eq!(
+            context.bech32_decode("osmo", encoded),
+            Err(Bech32Error::WrongHrp)
+        );
+    }
+
+    #[test]
+    fn test_fold_prefix() {
+        let mut context = Context::with_actor(Address::default());
+        let prefix = b"balance/";
+        let invalid = FoldError::InvalidValue {
+            key: b"balance/a".to_vec(),
+        };
+
+        context.mock_fold_prefix(prefix, FoldOp::Count, 3u64);
+        context.mock_fold_prefix(prefix, FoldOp::SumU64, Err::<u64, _>(&invalid));
+        context.mock_fold_prefix(
+            prefix,
+            FoldOp::Keys { limit: 1 },
+            vec![b"balance/a".to_vec()],
+        );
+
+        assert_eq!(context.count_prefix(prefix), 3);
+        assert_eq!(context.sum_prefix_u64(prefix), Err(invalid));
+        assert_eq!(context.prefix_keys(prefix, 1), [b"balance/a".to_vec()]);
+    }
+
+    #[test]
+    fn test_when_not_paused() {
+        let owner = Address::default();
+        let mut context = Context::with_actor(owner);
+        assert_eq!(guarded(&mut context), Ok(true));
+
+        context.pause(owner).unwrap();
+        assert_eq!(guarded(&mut context), Err(PAUSED.into()));
+
+        context.unpause(owner).unwrap();
+        assert_eq!(guarded(&mut context), Ok(true));
+    }
+
+    #[test]
+    fn test_emit_event() {
+        let actor = Address::new([1; Address::LEN]);
+        let mut context = Context::with_actor(actor);
+
+        emit_transfer(&mut context, 5);
+
+        let data = wasmlanche::borsh::to_vec(&(actor, 5u64)).unwrap();
+        assert_eq!(context.emitted_events(), [("Transfer".to_string(), data)]);
+    }
+
+    #[test]
+    fn test_pick_winners() {
+        let entrants: Vec<_> = (0..10).map(|i| Address::new([i; Address::LEN])).collect();
+
+        let first = pick_winners(
+            &mut Context::with_actor(Address::default()),
+            entrants.clone(),
+        );
+        let second = pick_winners(
+            &mut Context::with_actor(Address::default()),
+            entrants.clone(),
+        );
+
+        assert!(first == second);
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|winner| entrants.contains(winner)));
+        assert!(first[0] != first[1] && first[1] != first[2] && first[0] != first[2]);
+    }
+
+    #[test]
+    fn test_call_typed() {
+        let target = Address::new([2; Address::LEN]);
+        let mut context = Context::with_actor(Address::default());
+
+        context.mock_function_call(target, "add", (1i64, 2i64), 0, 3i64);
+
+        let sum: i64 = context
+            .call_typed(target, "add", &(1i64, 2i64), 0, 0)
+            .unwrap();
+        assert_eq!(sum, 3);
+    }
+
+    #[test]
+    fn test_public_dispatch() {
+        use wasmlanche::borsh;
+
+        let to = Address::new([3; Address::LEN]);
+        let mut context = Context::with_actor(Address::default());
+        context.mock_set_balance(to, 10);
+
+        for (action, export) in [
+            (
+                Action::Deposit { to, amount: 5 },
+                actions::deposit(&mut context, to, 5),
+            ),
+            (Action::Double(21), actions::double(&mut context, 21)),
+        ] {
+            // `execute` receives the action borsh-encoded, like any other argument
+            let encoded = borsh::to_vec(&action).unwrap();
+            let action: Action = borsh::from_slice(&encoded).unwrap();
+            let result = actions::execute(&mut context, action).into_inner();
+
+            assert_eq!(result, borsh::to_vec(&export).unwrap());
+            assert_eq!(borsh::from_slice::<u64>(&result).unwrap(), export);
+        }
+
+        assert_eq!(actions::deposit(&mut context, to, 5), 15);
+        assert_eq!(actions::double(&mut context, 21), 42);
+    }
+
+    #[test]
+    fn test_public_dispatch_exports() {
+        // `deposit` reads a balance, which contexts the host passes in cannot mock
+        let actor = Address::default();
+        let args = borsh::to_vec(&21u64).unwrap();
+
+        let dispatched = host::call(
+            exports::execute,
+            actor,
+            &borsh::to_vec(&Action::Double(21)).unwrap(),
+        );
+        let direct = host::call(exports::double, actor, &args);
+
+        assert_eq!(dispatched, direct);
+        assert_eq!(borsh::from_slice::<u64>(&direct).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_ensure_reverts_reach_the_caller() {
+        let mint = |actor, amount: u64| {
+            let args = borsh::to_vec(&amount).unwrap();
+            let result = host::call(exports::mint, actor, &args);
+            borsh::from_slice::<Result<u64, Revert>>(&result).unwrap()
+        };
+
+        assert_eq!(mint(minter(), 5), Ok(5));
+
+        let err = mint(Address::default(), 5).unwrap_err();
+        assert_eq!(err.code(), errors::NOT_MINTER.code());
+        assert_eq!(err.message(), errors::NOT_MINTER.reason());
+
+        let err = mint(minter(), 0).unwrap_err();
+        assert_eq!(err.code(), errors::ZERO_AMOUNT.code());
+        assert_eq!(err.message(), "cannot mint 0 tokens");
+    }
+
+    #[test]
+    fn test_old_encodings_use_trailing_defaults() {
+        let to = Address::new([3; Address::LEN]);
+        let old = borsh::to_vec(&(to, 5u64)).unwrap();
+
+        let result = host::call(exports::transfer, Address::default(), &old);
+        let result: (u64, Option<String>) = borsh::from_slice(&result).unwrap();
+        assert_eq!(result, (5, None));
+
+        let result = host::call(exports::transfer_with_fee, Address::default(), &old);
+        assert_eq!(borsh::from_slice::<u64>(&result).unwrap(), 15);
+
+        let new = borsh::to_vec(&(to, 5u64, Some("rent"))).unwrap();
+        let result = host::call(exports::transfer, Address::default(), &new);
+        let result: (u64, Option<String>) = borsh::from_slice(&result).unwrap();
+        assert_eq!(result, (5, Some("rent".to_string())));
+    }
+
+    #[test]
+    fn test_strict_args_reject_old_encodings() {
+        let to = Address::new([3; Address::LEN]);
+        let old = borsh::to_vec(&(to, 5u64)).unwrap();
+
+        let panic = std::panic::catch_unwind(|| {
+            host::call(exports::strict_transfer, Address::default(), &old)
+        })
+        .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.starts_with("error fetching serialized args"),
+            "{message}"
+        );
+
+        let new = borsh::to_vec(&(to, 5u64, None::<String>)).unwrap();
+        let result = host::call(exports::strict_transfer, Address::default(), &new);
+        let result: (u64, Option<String>) = borsh::from_slice(&result).unwrap();
+        assert_eq!(result, (5, None));
+    }
+
+    #[test]
+    fn test_block_height() {
+        let mut context = Context::with_actor(Address::default());
+        assert_eq!(block_height(&mut context), 0);
+
+        let block = BlockInfo {
+            height: 42,
+            timestamp: 1_700_000_000,
+        };
+        context.set_block_info(block);
+
+        assert_eq!(block_height(&mut context), 42);
+        assert_eq!(context.timestamp(), 1_700_000_000);
+        assert_eq!(context.block_info(), block);
+    }
 }